@@ -0,0 +1,589 @@
+//! Secondary actions on a result, listed with Cmd/Ctrl+K next to the Enter
+//! behavior `useLauncher.launch` picks.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::indexer::ResultType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Open,
+    /// Needs the `exec` of the app to open with
+    OpenWith,
+    Reveal,
+    CopyPath,
+    CopyName,
+    OpenInTerminal,
+    /// Needs the new name
+    Rename,
+    Duplicate,
+    /// Needs the folder to move into
+    MoveTo,
+    /// Needs the folder to copy into
+    CopyTo,
+    Trash,
+    Launch,
+    LaunchNewInstance,
+    ShowDesktopFile,
+    QuitInstances,
+    Run,
+    RunInTerminal,
+    CopyOutput,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Action {
+    pub kind: ActionKind,
+    pub label: &'static str,
+    /// The frontend asks for confirmation first
+    pub destructive: bool,
+}
+
+/// The result an action applies to, as the frontend has it.
+#[derive(Debug, Deserialize)]
+pub struct ActionTarget {
+    pub name: String,
+    pub exec: String,
+    pub result_type: ResultType,
+}
+
+const PATH_ACTIONS: &[ActionKind] = &[
+    ActionKind::Open,
+    ActionKind::OpenWith,
+    ActionKind::Reveal,
+    ActionKind::CopyPath,
+    ActionKind::CopyName,
+    ActionKind::OpenInTerminal,
+    ActionKind::Rename,
+    ActionKind::Duplicate,
+    ActionKind::MoveTo,
+    ActionKind::CopyTo,
+    ActionKind::Trash,
+];
+
+const APP_ACTIONS: &[ActionKind] = &[
+    ActionKind::Launch,
+    ActionKind::LaunchNewInstance,
+    ActionKind::ShowDesktopFile,
+    ActionKind::QuitInstances,
+];
+
+const COMMAND_ACTIONS: &[ActionKind] = &[ActionKind::Run, ActionKind::RunInTerminal];
+
+const SHELL_ACTIONS: &[ActionKind] = &[ActionKind::CopyOutput];
+
+impl ActionKind {
+    fn label(self) -> &'static str {
+        match self {
+            ActionKind::Open => "Open",
+            ActionKind::OpenWith => "Open With…",
+            #[cfg(target_os = "macos")]
+            ActionKind::Reveal => "Reveal in Finder",
+            #[cfg(not(target_os = "macos"))]
+            ActionKind::Reveal => "Reveal in File Manager",
+            ActionKind::CopyPath => "Copy Path",
+            ActionKind::CopyName => "Copy Name",
+            ActionKind::OpenInTerminal => "Open in Terminal",
+            ActionKind::Rename => "Rename…",
+            ActionKind::Duplicate => "Duplicate",
+            ActionKind::MoveTo => "Move to…",
+            ActionKind::CopyTo => "Copy to…",
+            ActionKind::Trash => "Move to Trash",
+            ActionKind::Launch => "Launch",
+            ActionKind::LaunchNewInstance => "Launch New Instance",
+            #[cfg(target_os = "macos")]
+            ActionKind::ShowDesktopFile => "Show Application in Finder",
+            #[cfg(not(target_os = "macos"))]
+            ActionKind::ShowDesktopFile => "Show Desktop File",
+            ActionKind::QuitInstances => "Quit Running Instances",
+            ActionKind::Run => "Run",
+            ActionKind::RunInTerminal => "Run in Terminal",
+            ActionKind::CopyOutput => "Copy Output",
+        }
+    }
+
+    /// Whether the action applies to paths rather than apps.
+    pub fn takes_path(self) -> bool {
+        PATH_ACTIONS.contains(&self)
+    }
+}
+
+/// Actions offered for a result, default first.
+pub fn for_result(result_type: &ResultType) -> Vec<Action> {
+    let kinds = match result_type {
+        ResultType::Folder | ResultType::Image | ResultType::File => PATH_ACTIONS,
+        ResultType::App => APP_ACTIONS,
+        ResultType::Command => COMMAND_ACTIONS,
+        ResultType::Shell => SHELL_ACTIONS,
+        ResultType::System
+        | ResultType::Calculator
+        | ResultType::Script
+        | ResultType::WebSearch
+        | ResultType::Quicklink => &[],
+    };
+    kinds
+        .iter()
+        .map(|&kind| Action {
+            kind,
+            label: kind.label(),
+            destructive: matches!(kind, ActionKind::Trash | ActionKind::QuitInstances),
+        })
+        .collect()
+}
+
+/// Show `path` selected in the file manager.
+pub fn reveal(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open").arg("-R").arg(path).spawn()?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // FileManager1 selects the item; without it, open the parent folder
+        let uri = file_uri(path);
+        let shown = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", uri))
+            .arg("string:")
+            .status()
+            .is_ok_and(|s| s.success());
+        if !shown {
+            let dir = path.parent().unwrap_or(path);
+            Command::new("xdg-open").arg(dir).spawn()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `file://` URI for an absolute path.
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn file_uri(path: &Path) -> String {
+    format!("file://{}", percent_encode(path))
+}
+
+/// Percent-encode what URIs don't allow in a path, as `file://` URIs and
+/// `.trashinfo` files want.
+#[cfg(any(target_os = "linux", test))]
+pub(crate) fn percent_encode(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.to_string_lossy().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Terminals to try, in order, after the configured one and `$TERMINAL`.
+#[cfg(target_os = "linux")]
+const TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "foot",
+    "wezterm",
+    "xterm",
+];
+
+/// Start the first terminal found, `preferred` and `$TERMINAL` first.
+/// `build` adds the arguments, given the terminal's name.
+#[cfg(target_os = "linux")]
+fn spawn_terminal(preferred: Option<&str>, build: impl Fn(&mut Command, &str)) -> io::Result<()> {
+    let env = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+    let candidates = preferred
+        .into_iter()
+        .chain(env.as_deref())
+        .chain(TERMINALS.iter().copied());
+    let mut last = io::Error::new(io::ErrorKind::NotFound, "no terminal emulator found");
+    for terminal in candidates {
+        let mut command = Command::new(terminal);
+        build(&mut command, terminal);
+        match command.spawn() {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+/// How a terminal takes the program to run instead of a shell.
+#[cfg(any(target_os = "linux", test))]
+fn exec_args(terminal: &str) -> &'static [&'static str] {
+    let name = Path::new(terminal)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(terminal);
+    match name {
+        "gnome-terminal" | "kgx" | "ptyxis" => &["--"],
+        "xfce4-terminal" => &["-x"],
+        "wezterm" => &["start", "--"],
+        "kitty" | "foot" => &[],
+        _ => &["-e"],
+    }
+}
+
+/// Open a terminal in `dir`.
+#[cfg(target_os = "linux")]
+pub fn open_terminal(dir: &Path, preferred: Option<&str>) -> io::Result<()> {
+    // Terminals start their shell in the working directory
+    spawn_terminal(preferred, |command, _| {
+        command.current_dir(dir);
+    })
+}
+
+/// Run `program` with `args` in a new terminal window, from the home folder.
+#[cfg(target_os = "linux")]
+pub fn run_in_terminal(program: &Path, args: &[String], preferred: Option<&str>) -> io::Result<()> {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    spawn_terminal(preferred, |command, terminal| {
+        command
+            .current_dir(&home)
+            .args(exec_args(terminal))
+            .arg(program)
+            .args(args);
+    })
+}
+
+/// Open a terminal in `dir`.
+#[cfg(target_os = "macos")]
+pub fn open_terminal(dir: &Path, preferred: Option<&str>) -> io::Result<()> {
+    Command::new("open")
+        .args(["-a", preferred.unwrap_or("Terminal")])
+        .arg(dir)
+        .spawn()
+        .map(|_| ())
+}
+
+/// Run `program` with `args` in a new Terminal window. Only Terminal.app
+/// takes a command over AppleScript, so `preferred` isn't used.
+#[cfg(target_os = "macos")]
+pub fn run_in_terminal(
+    program: &Path,
+    args: &[String],
+    _preferred: Option<&str>,
+) -> io::Result<()> {
+    let mut words = vec![program.to_string_lossy().to_string()];
+    words.extend(args.iter().cloned());
+    let line = crate::cmdline::join(&words)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    let script = format!(
+        "tell application \"Terminal\"\nactivate\ndo script \"{}\"\nend tell",
+        line
+    );
+    Command::new("osascript")
+        .args(["-e", &script])
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn open_terminal(_dir: &Path, _preferred: Option<&str>) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn run_in_terminal(
+    _program: &Path,
+    _args: &[String],
+    _preferred: Option<&str>,
+) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Show a desktop notification.
+#[cfg(target_os = "linux")]
+pub fn notify(title: &str, body: &str) -> io::Result<()> {
+    Command::new("notify-send")
+        .args(["--app-name=Cheru", "--", title, body])
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+pub fn notify(title: &str, body: &str) -> io::Result<()> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!(
+        "display notification {} with title {}",
+        quote(body),
+        quote(title)
+    );
+    Command::new("osascript")
+        .args(["-e", &script])
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn notify(_title: &str, _body: &str) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Programs that start other programs. Their processes are shared by every
+/// app launched through them, so Quit All Instances can't target them.
+#[cfg(any(not(target_os = "macos"), test))]
+const WRAPPERS: &[&str] = &[
+    "sh",
+    "bash",
+    "dash",
+    "zsh",
+    "fish",
+    "env",
+    "flatpak",
+    "snap",
+    "sudo",
+    "pkexec",
+    "nohup",
+    "xdg-open",
+    "gtk-launch",
+    "python",
+    "python3",
+    "perl",
+    "ruby",
+    "node",
+    "java",
+];
+
+/// What Quit All Instances signals for an app.
+#[cfg(any(not(target_os = "macos"), test))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuitTarget {
+    /// Processes running this program, by name or path
+    Program(String),
+    /// A Flatpak app, stopped with `flatpak kill`
+    Flatpak(String),
+    /// A Snap, whose processes run from `/snap/<name>/`
+    Snap(String),
+}
+
+/// Find the app an Exec line (without field codes) really starts, looking
+/// through `env VAR=…`, `flatpak run` and `/snap/bin/` launchers.
+#[cfg(any(not(target_os = "macos"), test))]
+pub fn quit_target(exec: &str) -> Result<QuitTarget, String> {
+    let mut words = exec.split_whitespace().peekable();
+    let mut program = words.next().ok_or("Nothing to quit")?;
+    if base_name(program) == "env" {
+        while words
+            .peek()
+            .is_some_and(|w| w.contains('=') || w.starts_with('-'))
+        {
+            words.next();
+        }
+        program = words.next().ok_or("Nothing to quit")?;
+    }
+
+    if base_name(program) == "flatpak" && words.next() == Some("run") {
+        if let Some(app_id) = words.find(|w| !w.starts_with('-')) {
+            return Ok(QuitTarget::Flatpak(app_id.to_string()));
+        }
+    }
+    if let Some(command) = program.strip_prefix("/snap/bin/") {
+        // `/snap/bin/<snap>.<app>` runs an app of `<snap>`
+        let name = command.split('.').next().unwrap_or(command);
+        return Ok(QuitTarget::Snap(name.to_string()));
+    }
+    if WRAPPERS.contains(&base_name(program)) {
+        return Err(format!(
+            "Started through {}, so its instances can't be told apart",
+            base_name(program)
+        ));
+    }
+    Ok(QuitTarget::Program(program.to_string()))
+}
+
+#[cfg(any(not(target_os = "macos"), test))]
+fn base_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/// Ask every running copy of `target` to quit.
+#[cfg(target_os = "linux")]
+pub fn quit_instances(target: &QuitTarget) -> io::Result<usize> {
+    let program = match target {
+        QuitTarget::Flatpak(app_id) => {
+            // Fails when the app isn't running
+            let status = Command::new("flatpak").args(["kill", app_id]).status()?;
+            return Ok(status.success() as usize);
+        }
+        QuitTarget::Snap(name) => Path::new("/snap").join(name),
+        QuitTarget::Program(program) => find_program(program)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("Cannot find {}", program))
+            })?
+            .canonicalize()?,
+    };
+
+    let mut count = 0;
+    for entry in std::fs::read_dir("/proc")?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        // Only our own processes have a readable exe link
+        let Ok(exe) = std::fs::read_link(entry.path().join("exe")) else {
+            continue;
+        };
+        let args: Vec<String> = std::fs::read(entry.path().join("cmdline"))
+            .unwrap_or_default()
+            .split(|&b| b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+        let is_instance = match target {
+            QuitTarget::Snap(_) => exe.starts_with(&program),
+            _ => exe == program || runs_script(&exe, &args, &program),
+        };
+        if is_instance && pid as u32 != std::process::id() {
+            // SAFETY: kill has no memory-safety preconditions
+            count += (unsafe { libc::kill(pid, libc::SIGTERM) } == 0) as usize;
+        }
+    }
+    Ok(count)
+}
+
+/// Ask the app bundle at `bundle` to quit, if it is running.
+#[cfg(target_os = "macos")]
+pub fn quit_instances(bundle: &Path) -> io::Result<usize> {
+    let app = bundle
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    // Telling an app that isn't running to quit would launch it first
+    let script = format!(
+        "if application \"{0}\" is running then\ntell application \"{0}\" to quit\nreturn 1\nend if\nreturn 0",
+        app
+    );
+    let output = Command::new("osascript").args(["-e", &script]).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0))
+}
+
+/// Whether a process is an interpreter running `script`, which it gets as
+/// its first argument.
+#[cfg(target_os = "linux")]
+fn runs_script(exe: &Path, args: &[String], script: &Path) -> bool {
+    let interpreter = exe.file_name().and_then(|n| n.to_str());
+    interpreter.is_some_and(|n| WRAPPERS.contains(&n))
+        && args
+            .get(1)
+            .and_then(|arg| Path::new(arg).canonicalize().ok())
+            .is_some_and(|arg| arg == script)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn quit_instances(_target: &QuitTarget) -> io::Result<usize> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Resolve a bare program name through `$PATH` to a file that may be run,
+/// as a shell would.
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Some(PathBuf::from(program));
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        // Empty and relative entries would search the working folder
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(program))
+        .find(|candidate| crate::indexer::is_executable(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions_per_result_type() {
+        let kinds = |t: ResultType| -> Vec<ActionKind> {
+            for_result(&t).into_iter().map(|a| a.kind).collect()
+        };
+        assert_eq!(kinds(ResultType::Folder), PATH_ACTIONS);
+        assert_eq!(kinds(ResultType::File)[0], ActionKind::Open);
+        assert_eq!(kinds(ResultType::App)[0], ActionKind::Launch);
+        assert!(kinds(ResultType::System).is_empty());
+
+        let trash = for_result(&ResultType::Image)
+            .into_iter()
+            .find(|a| a.kind == ActionKind::Trash)
+            .unwrap();
+        assert!(trash.destructive);
+        assert!(ActionKind::CopyName.takes_path());
+        assert!(!ActionKind::QuitInstances.takes_path());
+        assert_eq!(kinds(ResultType::Command), COMMAND_ACTIONS);
+        assert!(!ActionKind::Run.takes_path());
+    }
+
+    #[test]
+    fn test_terminal_exec_args() {
+        assert_eq!(exec_args("gnome-terminal"), ["--"]);
+        assert_eq!(exec_args("/usr/bin/xfce4-terminal"), ["-x"]);
+        assert_eq!(exec_args("kitty"), [] as [&str; 0]);
+        assert_eq!(exec_args("alacritty"), ["-e"]);
+    }
+
+    #[test]
+    fn test_quit_target() {
+        let program = |p: &str| Ok(QuitTarget::Program(p.to_string()));
+        assert_eq!(
+            quit_target("/usr/bin/firefox --new-window"),
+            program("/usr/bin/firefox")
+        );
+        assert_eq!(quit_target("env -i GDK_BACKEND=x11 gimp"), program("gimp"));
+        assert_eq!(
+            quit_target("/usr/bin/flatpak run --branch=stable --command=app org.gimp.GIMP"),
+            Ok(QuitTarget::Flatpak("org.gimp.GIMP".to_string()))
+        );
+        assert_eq!(
+            quit_target(
+                "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/x.desktop /snap/bin/code.url-handler"
+            ),
+            Ok(QuitTarget::Snap("code".to_string()))
+        );
+        assert!(quit_target("sh -c \"cd ~/app && ./run\"").is_err());
+        assert!(quit_target("/usr/bin/python3 -m notes").is_err());
+        assert!(quit_target("env FOO=1").is_err());
+    }
+
+    #[test]
+    fn test_file_uri() {
+        assert_eq!(
+            file_uri(Path::new("/home/me/My Notes/naïve.md")),
+            "file:///home/me/My%20Notes/na%C3%AFve.md"
+        );
+    }
+
+    #[test]
+    fn test_action_kind_names() {
+        assert_eq!(
+            serde_json::to_string(&ActionKind::OpenInTerminal).unwrap(),
+            "\"open_in_terminal\""
+        );
+        assert_eq!(
+            serde_json::from_str::<ActionKind>("\"show_desktop_file\"").unwrap(),
+            ActionKind::ShowDesktopFile
+        );
+    }
+}
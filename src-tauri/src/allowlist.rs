@@ -0,0 +1,103 @@
+//! Folders launched executables may live in. Checks run on the canonical
+//! path, so a symlink can't smuggle in a program from elsewhere.
+
+use std::path::{Path, PathBuf};
+
+/// Package managers and app folders each platform installs programs into.
+/// `~/` is the home directory.
+#[cfg(target_os = "linux")]
+const DEFAULT_DIRS: &[&str] = &[
+    "/usr/bin",
+    "/usr/local/bin",
+    "/usr/games",
+    "/opt",
+    "/var/lib/flatpak/exports/bin",
+    "/snap/bin",
+    "/nix/store",
+    "/run/current-system/sw/bin",
+    "~/.local/bin",
+    "~/.local/share/flatpak/exports/bin",
+    "~/.nix-profile/bin",
+    // AppImages
+    "~/Applications",
+];
+
+#[cfg(target_os = "macos")]
+const DEFAULT_DIRS: &[&str] = &[
+    "/Applications",
+    "/System/Applications",
+    "/usr/bin",
+    "/usr/local/bin",
+    "/opt",
+    "/nix/store",
+    "/run/current-system/sw/bin",
+    "~/Applications",
+    "~/.local/bin",
+    "~/.nix-profile/bin",
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+const DEFAULT_DIRS: &[&str] = &[];
+
+/// The platform's default allowlist.
+pub fn default_dirs() -> Vec<PathBuf> {
+    DEFAULT_DIRS.iter().filter_map(|d| expand_home(d)).collect()
+}
+
+/// Expand a leading `~/`. Relative paths aren't valid allowlist entries.
+pub fn expand_home(path: &str) -> Option<PathBuf> {
+    let expanded = match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()?.join(rest),
+        None => PathBuf::from(path),
+    };
+    expanded.is_absolute().then_some(expanded)
+}
+
+/// Whether the canonical path `exe` is inside one of `dirs`. Entries are
+/// compared as written and resolved, since `/bin` or `~/.nix-profile/bin`
+/// are often symlinks themselves.
+pub fn is_allowed(exe: &Path, dirs: &[PathBuf]) -> bool {
+    dirs.iter()
+        .any(|dir| exe.starts_with(dir) || dir.canonicalize().is_ok_and(|d| exe.starts_with(d)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_allowed_by_whole_components() {
+        let dirs = [PathBuf::from("/opt"), PathBuf::from("/usr/bin")];
+        assert!(is_allowed(Path::new("/opt/zed/bin/zed"), &dirs));
+        assert!(is_allowed(Path::new("/usr/bin/firefox"), &dirs));
+        assert!(!is_allowed(Path::new("/optional/evil"), &dirs));
+        assert!(!is_allowed(Path::new("/usr/binaries/evil"), &dirs));
+        assert!(!is_allowed(Path::new("/tmp/firefox"), &dirs));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_allowed_through_symlinked_dir() {
+        let dir = std::env::temp_dir().join(format!("cheru-test-allowlist-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("store/hello/bin")).unwrap();
+        std::os::unix::fs::symlink(dir.join("store/hello/bin"), dir.join("profile-bin")).unwrap();
+
+        let exe = dir.join("store/hello/bin/hello");
+        std::fs::write(&exe, "").unwrap();
+        let canonical = exe.canonicalize().unwrap();
+        assert!(is_allowed(&canonical, &[dir.join("profile-bin")]));
+        assert!(!is_allowed(&canonical, &[dir.join("elsewhere")]));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/.local/bin"), Some(home.join(".local/bin")));
+        assert_eq!(expand_home("/snap/bin"), Some(PathBuf::from("/snap/bin")));
+        assert_eq!(expand_home("bin"), None);
+        assert!(default_dirs().iter().all(|d| d.is_absolute()));
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::paths;
+
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    pub expression: String,
+    pub result: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl HistoryEntry {
+    pub fn new(expression: &str, result: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            expression: expression.trim().to_string(),
+            result: result.to_string(),
+            timestamp,
+        }
+    }
+}
+
+/// Load the calculation history, newest first.
+pub fn load() -> Vec<HistoryEntry> {
+    load_from(&history_path())
+}
+
+/// Record a committed calculation at the top of the history.
+pub fn append(entry: HistoryEntry) -> std::io::Result<()> {
+    append_to(&history_path(), entry)
+}
+
+fn load_from(path: &Path) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn append_to(path: &Path, entry: HistoryEntry) -> std::io::Result<()> {
+    let mut entries = load_from(path);
+    // Re-committing the same calculation moves it to the top
+    entries.retain(|e| e.expression != entry.expression || e.result != entry.result);
+    entries.insert(0, entry);
+    entries.truncate(MAX_ENTRIES);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&entries).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+fn history_path() -> PathBuf {
+    paths::data_dir().join("calc_history.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cheru-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("calc_history.json")
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let path = temp_path("missing");
+        assert!(load_from(&path).is_empty());
+    }
+
+    #[test]
+    fn test_append_newest_first() {
+        let path = temp_path("append");
+        append_to(&path, HistoryEntry::new("2+2", "4")).unwrap();
+        append_to(&path, HistoryEntry::new("x = 3.2 * 7", "22.4")).unwrap();
+        let entries = load_from(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].expression, "x = 3.2 * 7");
+        assert_eq!(entries[1].result, "4");
+    }
+
+    #[test]
+    fn test_append_deduplicates() {
+        let path = temp_path("dedup");
+        append_to(&path, HistoryEntry::new("2+2", "4")).unwrap();
+        append_to(&path, HistoryEntry::new("3*3", "9")).unwrap();
+        append_to(&path, HistoryEntry::new("2+2", "4")).unwrap();
+        let entries = load_from(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].expression, "2+2");
+    }
+}
@@ -1,139 +1,784 @@
-struct Parser {
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Name bound to the most recently committed result.
+pub const ANS: &str = "ans";
+
+/// Largest result, in bits, an exact power may produce before falling back to f64.
+const MAX_EXACT_POW_BITS: u64 = 100_000;
+
+/// A calculator value: an exact rational where possible, otherwise an f64
+/// produced by a transcendental function or a non-integer power.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Exact(BigRational),
+    Approx(f64),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Number::Approx(v) => *v,
+        }
+    }
+
+    fn add(self, rhs: Number) -> Number {
+        match (self, rhs) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a + b),
+            (a, b) => Number::Approx(a.to_f64() + b.to_f64()),
+        }
+    }
+
+    fn sub(self, rhs: Number) -> Number {
+        self.add(rhs.neg())
+    }
+
+    fn mul(self, rhs: Number) -> Number {
+        match (self, rhs) {
+            (Number::Exact(a), Number::Exact(b)) => Number::Exact(a * b),
+            (a, b) => Number::Approx(a.to_f64() * b.to_f64()),
+        }
+    }
+
+    fn div(self, rhs: Number) -> Option<Number> {
+        match (self, rhs) {
+            (_, Number::Exact(b)) if b.is_zero() => None,
+            (Number::Exact(a), Number::Exact(b)) => Some(Number::Exact(a / b)),
+            (a, b) => Some(Number::Approx(a.to_f64() / b.to_f64())),
+        }
+    }
+
+    fn neg(self) -> Number {
+        match self {
+            Number::Exact(a) => Number::Exact(-a),
+            Number::Approx(v) => Number::Approx(-v),
+        }
+    }
+
+    fn pow(self, exp: Number) -> Option<Number> {
+        if let (Number::Exact(base), Number::Exact(exp)) = (&self, &exp) {
+            if exp.is_integer() {
+                if let Some(e) = exp.to_integer().to_i32() {
+                    let bits = base.numer().bits().max(base.denom().bits());
+                    if bits.saturating_mul(u64::from(e.unsigned_abs())) <= MAX_EXACT_POW_BITS {
+                        if base.is_zero() && e < 0 {
+                            return None;
+                        }
+                        return Some(Number::Exact(base.pow(e)));
+                    }
+                }
+            }
+        }
+        Some(Number::Approx(self.to_f64().powf(exp.to_f64())))
+    }
+
+    fn sqrt(self) -> Number {
+        if let Number::Exact(ref r) = self {
+            if !r.is_negative() {
+                let (n, d) = (r.numer().sqrt(), r.denom().sqrt());
+                if &n * &n == *r.numer() && &d * &d == *r.denom() {
+                    return Number::Exact(BigRational::new(n, d));
+                }
+            }
+        }
+        Number::Approx(self.to_f64().sqrt())
+    }
+
+    fn percent(self) -> Number {
+        self.mul(Number::Exact(BigRational::new(BigInt::from(1), BigInt::from(100))))
+    }
+
+    fn is_finite(&self) -> bool {
+        match self {
+            Number::Exact(_) => true,
+            Number::Approx(v) => v.is_finite(),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(v: i64) -> Self {
+        Number::Exact(BigRational::from_integer(BigInt::from(v)))
+    }
+}
+
+/// How results are rendered: fraction digits and locale separators.
+#[derive(Debug, Clone)]
+pub struct Format {
+    precision: usize,
+    decimal: char,
+    thousands: Option<&'static str>,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::new(10, "en", false)
+    }
+}
+
+impl Format {
+    /// `locale` is a language tag such as `en`, `de-DE` or `fr-CH`; only the
+    /// separator conventions are taken from it.
+    pub fn new(precision: usize, locale: &str, group_digits: bool) -> Self {
+        let (thousands, decimal) = locale_separators(locale);
+        Self {
+            precision,
+            decimal,
+            thousands: group_digits.then_some(thousands),
+        }
+    }
+}
+
+fn locale_separators(locale: &str) -> (&'static str, char) {
+    let tag = locale.to_lowercase().replace('_', "-");
+    let (lang, region) = tag.split_once('-').unwrap_or((tag.as_str(), ""));
+    match (lang, region) {
+        (_, "ch") | (_, "li") => ("'", '.'),
+        ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el", _) => (".", ','),
+        ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu", _) => {
+            ("\u{a0}", ',')
+        }
+        _ => (",", '.'),
+    }
+}
+
+/// Session state for the calculator: user variables plus `ans`.
+#[derive(Debug, Default)]
+pub struct Context {
+    vars: HashMap<String, Number>,
+    format: Format,
+}
+
+impl Context {
+    pub fn with_format(format: Format) -> Self {
+        Self {
+            vars: HashMap::new(),
+            format,
+        }
+    }
+
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    pub fn get(&self, name: &str) -> Option<Number> {
+        self.vars.get(name).cloned().or_else(|| constant(name))
+    }
+
+    /// Bind the evaluation's value to `ans` and, for assignments, to its variable.
+    pub fn commit(&mut self, eval: &Evaluation) {
+        if let Some(ref name) = eval.assign {
+            self.vars.insert(name.clone(), eval.value.clone());
+        }
+        self.vars.insert(ANS.to_string(), eval.value.clone());
+    }
+}
+
+/// A successfully evaluated expression, not yet committed to the context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub value: Number,
+    pub display: String,
+    /// Variable name when the input was `name = expr`.
+    pub assign: Option<String>,
+}
+
+fn constant(name: &str) -> Option<Number> {
+    match name {
+        "pi" => Some(Number::Approx(std::f64::consts::PI)),
+        "e" => Some(Number::Approx(std::f64::consts::E)),
+        _ => None,
+    }
+}
+
+const FUNCTIONS: &[&str] = &["sqrt", "abs", "sin", "cos", "tan", "ln", "log", "exp"];
+
+fn apply_function(name: &str, arg: Number) -> Option<Number> {
+    let x = arg.to_f64();
+    let value = match name {
+        "sqrt" => return Some(arg.sqrt()),
+        "abs" => {
+            return Some(match arg {
+                Number::Exact(r) => Number::Exact(r.abs()),
+                Number::Approx(v) => Number::Approx(v.abs()),
+            })
+        }
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "ln" => x.ln(),
+        "log" => x.log10(),
+        "exp" => x.exp(),
+        _ => return None,
+    };
+    Some(Number::Approx(value))
+}
+
+fn is_reserved(name: &str) -> bool {
+    name == ANS || constant(name).is_some() || FUNCTIONS.contains(&name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorKind {
+    /// Input ended where an operand or `)` was expected, e.g. `2+`
+    Incomplete,
+    UnclosedParen,
+    UnexpectedChar,
+    UnknownIdentifier,
+    DivisionByZero,
+    /// The result is not a finite number, e.g. `ln(0)`
+    Undefined,
+}
+
+/// Why an expression that looks like math failed, with the 0-based
+/// character position in the original input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalcError {
+    pub kind: ErrorKind,
+    pub position: usize,
+}
+
+impl std::fmt::Display for CalcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            ErrorKind::Incomplete => "Incomplete expression",
+            ErrorKind::UnclosedParen => "Unclosed parenthesis",
+            ErrorKind::UnexpectedChar => "Unexpected character",
+            ErrorKind::UnknownIdentifier => "Unknown name",
+            ErrorKind::DivisionByZero => "Division by zero",
+            ErrorKind::Undefined => "Result is undefined",
+        };
+        write!(f, "{} at column {}", what, self.position + 1)
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+type ParseResult = Result<Number, CalcError>;
+
+/// Words that act as operators after a percentage: `15% of 80`, `10% off 250`.
+const KEYWORDS: &[&str] = &["of", "off"];
+
+/// Map typographic operators to ASCII, and `x` between two operands to `*`,
+/// one char for one char so error positions still match the input.
+fn normalize(input: &str) -> Vec<char> {
+    let chars: Vec<char> = input.chars().collect();
+    let neighbour = |range: &mut dyn Iterator<Item = usize>| {
+        range.map(|j| chars[j]).find(|c| !c.is_whitespace())
+    };
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| match c {
+            '×' | '·' | '∗' => '*',
+            '÷' | '∕' => '/',
+            '−' => '-',
+            'x' | 'X' => {
+                let before = neighbour(&mut (0..i).rev());
+                let after = neighbour(&mut (i + 1..chars.len()));
+                let in_word = chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic())
+                    || (i > 0 && chars[i - 1].is_ascii_alphabetic());
+                if !in_word
+                    && before.is_some_and(|b| b.is_ascii_digit() || b == ')' || b == '%')
+                    && after.is_some_and(|a| a.is_ascii_digit() || a == '(' || a == '.')
+                {
+                    '*'
+                } else {
+                    c
+                }
+            }
+            _ => c,
+        })
+        .collect()
+}
+
+struct Parser<'a> {
     chars: Vec<char>,
+    /// Position of `chars[0]` in the original input
+    base: usize,
     pos: usize,
+    /// Where a result first stopped being finite, as in `1 + ln(0)`
+    undefined_at: Option<usize>,
+    ctx: &'a Context,
 }
 
-impl Parser {
-    fn new(input: &str) -> Self {
+impl<'a> Parser<'a> {
+    /// `base` is the position of `input` within the full query, so errors in
+    /// the right-hand side of an assignment point at the right column.
+    fn new(input: &str, base: usize, ctx: &'a Context) -> Self {
         Self {
-            chars: input.chars().filter(|c| !c.is_whitespace()).collect(),
+            chars: normalize(input),
+            base,
             pos: 0,
+            undefined_at: None,
+            ctx,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
         }
     }
 
-    fn peek(&self) -> Option<char> {
+    /// Next significant char. Whitespace only separates tokens.
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
         self.chars.get(self.pos).copied()
     }
 
     fn next(&mut self) -> Option<char> {
-        let c = self.chars.get(self.pos).copied();
+        let c = self.peek();
         self.pos += 1;
         c
     }
 
-    fn parse_expr(&mut self) -> Option<f64> {
-        let mut left = self.parse_term()?;
+    /// Char at the cursor without skipping whitespace, for reading a token.
+    fn current(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn error(&self, kind: ErrorKind, pos: usize) -> CalcError {
+        CalcError {
+            kind,
+            position: self.base + pos.min(self.chars.len()),
+        }
+    }
+
+    /// Pass `value` through, noting `pos` if it is the first result that
+    /// isn't finite, so an undefined answer points at its cause.
+    fn track(&mut self, value: Number, pos: usize) -> Number {
+        if self.undefined_at.is_none() && !value.is_finite() {
+            self.undefined_at = Some(pos);
+        }
+        value
+    }
+
+    /// Error for a missing operand at the current position.
+    fn unexpected(&mut self) -> CalcError {
+        match self.peek() {
+            None => self.error(ErrorKind::Incomplete, self.pos),
+            Some(_) => self.error(ErrorKind::UnexpectedChar, self.pos),
+        }
+    }
+
+    /// Consume `of`/`off` if it is the next word.
+    fn keyword(&mut self) -> Option<&'static str> {
+        self.skip_whitespace();
+        let word: String = self.chars[self.pos..]
+            .iter()
+            .take_while(|c| is_ident_char(**c))
+            .collect();
+        let keyword = KEYWORDS.iter().copied().find(|k| *k == word)?;
+        self.pos += keyword.len();
+        Some(keyword)
+    }
+
+    /// Whether the next token starts an operand that multiplies implicitly,
+    /// as in `2(3+4)`, `3pi` or `2 sqrt(9)`.
+    fn at_implicit_factor(&mut self) -> bool {
+        match self.peek() {
+            Some('(') => true,
+            Some(c) if is_ident_start(c) => {
+                let word: String = self.chars[self.pos..]
+                    .iter()
+                    .take_while(|c| is_ident_char(**c))
+                    .collect();
+                !KEYWORDS.contains(&word.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_expr(&mut self) -> ParseResult {
+        let (mut left, _) = self.parse_term()?;
         while let Some(op) = self.peek() {
             if op == '+' || op == '-' {
+                let op_pos = self.pos;
                 self.next();
-                let right = self.parse_term()?;
-                left = if op == '+' { left + right } else { left - right };
+                let (mut right, is_percent) = self.parse_term()?;
+                // `80 + 15%` adds 15% of 80
+                if is_percent {
+                    right = left.clone().mul(right);
+                }
+                let sum = if op == '+' { left.add(right) } else { left.sub(right) };
+                left = self.track(sum, op_pos);
             } else {
                 break;
             }
         }
-        Some(left)
+        Ok(left)
     }
 
-    fn parse_term(&mut self) -> Option<f64> {
-        let mut left = self.parse_power()?;
-        while let Some(op) = self.peek() {
-            if op == '*' || op == '/' {
-                self.next();
-                let right = self.parse_power()?;
-                left = if op == '*' { left * right } else { left / right };
-            } else {
-                break;
+    /// Returns the product and whether it was a bare percentage like `15%`.
+    fn parse_term(&mut self) -> Result<(Number, bool), CalcError> {
+        let (mut left, mut is_percent) = self.parse_percent()?;
+        loop {
+            match self.peek() {
+                Some(op @ ('*' | '/')) => {
+                    let op_pos = self.pos;
+                    self.next();
+                    let (right, _) = self.parse_percent()?;
+                    let product = if op == '*' {
+                        left.mul(right)
+                    } else {
+                        left.div(right)
+                            .ok_or_else(|| self.error(ErrorKind::DivisionByZero, op_pos))?
+                    };
+                    left = self.track(product, op_pos);
+                }
+                _ if self.at_implicit_factor() => {
+                    let factor_pos = self.pos;
+                    let (right, _) = self.parse_percent()?;
+                    left = self.track(left.mul(right), factor_pos);
+                }
+                _ => break,
+            }
+            is_percent = false;
+        }
+        Ok((left, is_percent))
+    }
+
+    /// `15%`, `15% of 80`, `10% off 250`
+    fn parse_percent(&mut self) -> Result<(Number, bool), CalcError> {
+        let value = self.parse_power()?;
+        if self.peek() != Some('%') {
+            return Ok((value, false));
+        }
+        self.next();
+        let fraction = value.percent();
+        match self.keyword() {
+            Some("of") => {
+                let (base, _) = self.parse_percent()?;
+                Ok((fraction.mul(base), false))
+            }
+            Some("off") => {
+                let (base, _) = self.parse_percent()?;
+                let discount = base.clone().mul(fraction);
+                Ok((base.sub(discount), false))
             }
+            _ => Ok((fraction, true)),
         }
-        Some(left)
     }
 
-    fn parse_power(&mut self) -> Option<f64> {
+    fn parse_power(&mut self) -> ParseResult {
         let base = self.parse_unary()?;
         if self.peek() == Some('^') {
+            let op_pos = self.pos;
             self.next();
             let exp = self.parse_power()?;
-            Some(base.powf(exp))
+            let power = base
+                .pow(exp)
+                .ok_or_else(|| self.error(ErrorKind::DivisionByZero, op_pos))?;
+            Ok(self.track(power, op_pos))
         } else {
-            Some(base)
+            Ok(base)
         }
     }
 
-    fn parse_unary(&mut self) -> Option<f64> {
+    fn parse_unary(&mut self) -> ParseResult {
         if self.peek() == Some('-') {
             self.next();
-            Some(-self.parse_unary()?)
+            Ok(self.parse_unary()?.neg())
         } else {
             self.parse_atom()
         }
     }
 
-    fn parse_atom(&mut self) -> Option<f64> {
+    fn parse_atom(&mut self) -> ParseResult {
         if self.peek() == Some('(') {
+            let open = self.pos;
             self.next();
             let val = self.parse_expr()?;
-            if self.next() != Some(')') {
-                return None;
+            self.expect_close(open)?;
+            Ok(val)
+        } else if self.peek().is_some_and(is_ident_start) {
+            let start = self.pos;
+            let name = self.parse_ident();
+            if self.peek() == Some('(') && FUNCTIONS.contains(&name.as_str()) {
+                let open = self.pos;
+                self.next();
+                let arg = self.parse_expr()?;
+                self.expect_close(open)?;
+                let value = apply_function(&name, arg)
+                    .ok_or_else(|| self.error(ErrorKind::UnknownIdentifier, start))?;
+                Ok(self.track(value, start))
+            } else {
+                self.ctx
+                    .get(&name)
+                    .ok_or_else(|| self.error(ErrorKind::UnknownIdentifier, start))
             }
-            Some(val)
         } else {
             self.parse_number()
         }
     }
 
-    fn parse_number(&mut self) -> Option<f64> {
-        let start = self.pos;
-        while let Some(c) = self.peek() {
-            if c.is_ascii_digit() || c == '.' {
+    fn expect_close(&mut self, open: usize) -> Result<(), CalcError> {
+        match self.peek() {
+            Some(')') => {
                 self.next();
-            } else {
-                break;
+                Ok(())
             }
+            None => Err(self.error(ErrorKind::UnclosedParen, open)),
+            Some(_) => Err(self.error(ErrorKind::UnexpectedChar, self.pos)),
+        }
+    }
+
+    fn parse_ident(&mut self) -> String {
+        let start = self.pos;
+        while self.current().is_some_and(is_ident_char) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn parse_number(&mut self) -> ParseResult {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.current().is_some_and(|c| c.is_ascii_digit() || c == '.' || c == ',') {
+            self.pos += 1;
         }
         if self.pos == start {
-            return None;
+            return Err(self.unexpected());
         }
         let s: String = self.chars[start..self.pos].iter().collect();
-        s.parse().ok()
+        parse_localized(&s, self.ctx.format.decimal)
+            .map(Number::Exact)
+            .ok_or_else(|| self.error(ErrorKind::UnexpectedChar, start))
     }
 }
 
-pub fn evaluate(input: &str) -> Option<String> {
-    if input.is_empty() {
+/// Parse a number written with `.` or `,` separators. When both appear the
+/// last one is the decimal point. A lone separator is a thousands separator
+/// only if it is not the locale's decimal char and every group after it has
+/// exactly three digits, so `1,000` is 1000 but `3,5` is 3.5 in English.
+fn parse_localized(raw: &str, decimal: char) -> Option<BigRational> {
+    let Some(last_sep) = raw.rfind(['.', ',']) else {
+        return parse_decimal(raw);
+    };
+    let has_both = raw.contains('.') && raw.contains(',');
+    let sep = raw[last_sep..].chars().next()?;
+
+    let decimal_sep = if has_both {
+        sep
+    } else {
+        let groups: Vec<&str> = raw.split(sep).collect();
+        let is_grouping = sep != decimal
+            && groups.len() > 1
+            && !groups[0].is_empty()
+            && groups[1..].iter().all(|g| g.len() == 3);
+        if is_grouping || groups.len() > 2 {
+            // Only grouping separators: an integer
+            let digits: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+            let valid = groups[1..].iter().all(|g| g.len() == 3);
+            return if valid { parse_decimal(&digits) } else { None };
+        }
+        sep
+    };
+
+    let (int_part, frac_part) = raw.split_at(last_sep);
+    let frac_part = &frac_part[decimal_sep.len_utf8()..];
+    if frac_part.contains(['.', ',']) {
         return None;
     }
-    // Quick check: must contain at least one digit and one operator or parens
-    let has_digit = input.chars().any(|c| c.is_ascii_digit());
-    let has_op = input.chars().any(|c| matches!(c, '+' | '-' | '*' | '/' | '^' | '(' | ')'));
-    if !has_digit || !has_op {
+    let group = if decimal_sep == '.' { ',' } else { '.' };
+    let int_digits: String = int_part.chars().filter(|&c| c != group).collect();
+    if int_digits.contains(['.', ',']) {
         return None;
     }
-    let mut parser = Parser::new(input);
-    let result = parser.parse_expr()?;
-    if parser.pos < parser.chars.len() {
+    parse_decimal(&format!("{}.{}", int_digits, frac_part))
+}
+
+/// Parse a decimal literal such as `3.25` exactly, as 325/100.
+fn parse_decimal(s: &str) -> Option<BigRational> {
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    if frac_part.contains('.') || (int_part.is_empty() && frac_part.is_empty()) {
         return None;
     }
-    format_number(result)
+    let digits = format!("{}{}", int_part, frac_part);
+    let numer: BigInt = digits.parse().ok()?;
+    let denom = BigInt::from(10).pow(frac_part.len() as u32);
+    Some(BigRational::new(numer, denom))
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
 }
 
-fn format_number(v: f64) -> Option<String> {
-    if v.is_nan() || v.is_infinite() {
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(is_ident_start) && chars.all(is_ident_char)
+}
+
+/// Split `name = expr` into its parts. `ans`, constants and functions are read-only.
+fn split_assignment(input: &str) -> Option<(&str, &str)> {
+    let (lhs, rhs) = input.split_once('=')?;
+    let name = lhs.trim();
+    if !is_ident(name) || is_reserved(name) {
         return None;
     }
-    if v == v.trunc() && v.abs() < 1e15 {
-        Some(format!("{}", v as i64))
-    } else {
-        let s = format!("{:.10}", v);
-        let s = s.trim_end_matches('0').trim_end_matches('.');
-        Some(s.to_string())
+    Some((name, rhs))
+}
+
+/// Whether `expr` is worth evaluating (and reporting errors for): it must
+/// reference a number or known name, contain an operator, parens, implicit
+/// multiplication or an assignment, and every word in it must be a known
+/// variable, constant, function or keyword. Plain-word queries like `firefox`
+/// or `foo-bar` are not math.
+fn looks_like_math(ctx: &Context, expr: &str, is_assignment: bool) -> bool {
+    let chars = normalize(expr);
+    let mut has_operand = false;
+    let mut has_op = is_assignment;
+    let mut prev_was_number = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() || c == '.' {
+            has_operand = true;
+            while i < chars.len() && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | ',')) {
+                i += 1;
+            }
+            prev_was_number = true;
+            continue;
+        }
+        if is_ident_start(c) {
+            let start = i;
+            while i < chars.len() && is_ident_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if ctx.get(&word).is_some() {
+                has_operand = true;
+            } else if !FUNCTIONS.contains(&word.as_str()) && !KEYWORDS.contains(&word.as_str()) {
+                return false;
+            }
+            // `3pi`
+            has_op |= prev_was_number;
+            prev_was_number = false;
+            continue;
+        }
+        has_op |= matches!(c, '+' | '-' | '*' | '/' | '^' | '%' | '(' | ')');
+        if !c.is_whitespace() {
+            prev_was_number = false;
+        }
+        i += 1;
+    }
+    has_operand && has_op
+}
+
+/// Evaluate `input` against the session variables in `ctx` without committing it.
+///
+/// Returns `None` when the input does not look like math at all, so callers
+/// can tell a typo in an expression from an ordinary search query.
+pub fn evaluate_in(ctx: &Context, input: &str) -> Option<Result<Evaluation, CalcError>> {
+    let leading = input.chars().take_while(|c| c.is_whitespace()).count();
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    let (assign, expr, base) = match split_assignment(input) {
+        Some((name, expr)) => {
+            let base = leading + input.chars().count() - expr.chars().count();
+            (Some(name.to_string()), expr, base)
+        }
+        None => (None, input, leading),
+    };
+
+    if !looks_like_math(ctx, expr, assign.is_some()) {
+        return None;
     }
+
+    let mut parser = Parser::new(expr, base, ctx);
+    Some(parser.parse_expr().and_then(|value| {
+        if !parser.at_end() {
+            return Err(parser.error(ErrorKind::UnexpectedChar, parser.pos));
+        }
+        let display = format_number(&value, &ctx.format)
+            .ok_or_else(|| parser.error(ErrorKind::Undefined, parser.undefined_at.unwrap_or(0)))?;
+        Ok(Evaluation {
+            value,
+            display,
+            assign,
+        })
+    }))
+}
+
+fn format_number(v: &Number, format: &Format) -> Option<String> {
+    if !v.is_finite() {
+        return None;
+    }
+    let exact = match v {
+        Number::Exact(r) => r.clone(),
+        // Large floats carry no meaningful digits past ~17 significant places
+        Number::Approx(f) if f.abs() >= 1e15 => return Some(format!("{:e}", f)),
+        Number::Approx(f) => BigRational::from_float(*f)?,
+    };
+
+    // Round half away from zero to `precision` fraction digits
+    let scale = BigInt::from(10).pow(format.precision as u32);
+    let scaled = (exact.abs() * BigRational::from_integer(scale.clone())).round().to_integer();
+    let int_part = (&scaled / &scale).to_string();
+    let frac_part = format!("{:0>width$}", (&scaled % &scale).to_string(), width = format.precision);
+    let frac_part = frac_part.trim_end_matches('0');
+
+    let mut out = String::new();
+    if exact.is_negative() && !scaled.is_zero() {
+        out.push('-');
+    }
+    match format.thousands {
+        Some(sep) => out.push_str(&group_digits(&int_part, sep)),
+        None => out.push_str(&int_part),
+    }
+    if !frac_part.is_empty() {
+        out.push(format.decimal);
+        out.push_str(frac_part);
+    }
+    Some(out)
+}
+
+fn group_digits(digits: &str, sep: &str) -> String {
+    let lead = match digits.len() % 3 {
+        0 => 3.min(digits.len()),
+        n => n,
+    };
+    let mut out = digits[..lead].to_string();
+    for chunk in digits.as_bytes()[lead..].chunks(3) {
+        out.push_str(sep);
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+    }
+    out
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn evaluate(input: &str) -> Option<String> {
+        evaluate_in(&Context::default(), input)
+            .and_then(Result::ok)
+            .map(|e| e.display)
+    }
+
+    fn error(input: &str) -> Option<(ErrorKind, usize)> {
+        match evaluate_in(&Context::default(), input)? {
+            Ok(_) => None,
+            Err(e) => Some((e.kind, e.position)),
+        }
+    }
+
     #[test]
     fn basic_arithmetic() {
         assert_eq!(evaluate("2+3"), Some("5".into()));
@@ -169,4 +814,177 @@ mod tests {
     fn division_by_zero() {
         assert_eq!(evaluate("1/0"), None);
     }
+
+    #[test]
+    fn assignment_and_variables() {
+        let mut ctx = Context::default();
+        let eval = evaluate_in(&ctx, "x = 3.2 * 7").unwrap().unwrap();
+        assert_eq!(eval.display, "22.4");
+        assert_eq!(eval.assign.as_deref(), Some("x"));
+
+        // Not visible until committed
+        assert_eq!(evaluate_in(&ctx, "x / 2"), None);
+        ctx.commit(&eval);
+        assert_eq!(evaluate_in(&ctx, "x / 2").unwrap().unwrap().display, "11.2");
+    }
+
+    #[test]
+    fn ans_tracks_last_commit() {
+        let mut ctx = Context::default();
+        ctx.commit(&evaluate_in(&ctx, "2+3").unwrap().unwrap());
+        assert_eq!(evaluate_in(&ctx, "ans*10").unwrap().unwrap().display, "50");
+        ctx.commit(&evaluate_in(&ctx, "ans*10").unwrap().unwrap());
+        assert_eq!(ctx.get(ANS), Some(Number::from(50)));
+    }
+
+    #[test]
+    fn ans_is_read_only() {
+        let ctx = Context::default();
+        assert_eq!(evaluate_in(&ctx, "ans = 4"), None);
+    }
+
+    #[test]
+    fn unknown_words_are_not_math() {
+        let mut ctx = Context::default();
+        ctx.commit(&evaluate_in(&ctx, "x = 1").unwrap().unwrap());
+        assert_eq!(evaluate_in(&ctx, "x"), None);
+        assert_eq!(evaluate_in(&ctx, "foo-bar"), None);
+        assert_eq!(evaluate_in(&ctx, "y + 1"), None);
+    }
+
+    #[test]
+    fn exact_decimals() {
+        assert_eq!(evaluate("0.1+0.2"), Some("0.3".into()));
+        assert_eq!(evaluate("1/3"), Some("0.3333333333".into()));
+        assert_eq!(evaluate("2/3"), Some("0.6666666667".into()));
+        assert_eq!(evaluate("(1/3)*3"), Some("1".into()));
+    }
+
+    #[test]
+    fn big_integers() {
+        assert_eq!(evaluate("2^70"), Some("1180591620717411303424".into()));
+        assert_eq!(evaluate("2^-2"), Some("0.25".into()));
+        assert_eq!(evaluate("0^-1"), None);
+    }
+
+    #[test]
+    fn transcendental_fallback() {
+        assert_eq!(evaluate("2^0.5"), Some("1.4142135624".into()));
+        assert_eq!(evaluate("sqrt(16)+1"), Some("5".into()));
+        assert_eq!(evaluate("sqrt(2)*1"), Some("1.4142135624".into()));
+        assert_eq!(evaluate("2*pi"), Some("6.2831853072".into()));
+        assert_eq!(evaluate("sin(0)+1"), Some("1".into()));
+        assert_eq!(evaluate("ln(0)+1"), None);
+    }
+
+    #[test]
+    fn constants_are_read_only() {
+        assert_eq!(evaluate("pi = 3"), None);
+    }
+
+    #[test]
+    fn precision_and_grouping() {
+        let ctx = Context::with_format(Format::new(2, "en", true));
+        assert_eq!(evaluate_in(&ctx, "1234567.891*1").unwrap().unwrap().display, "1,234,567.89");
+        assert_eq!(evaluate_in(&ctx, "-1000/3").unwrap().unwrap().display, "-333.33");
+
+        let ctx = Context::with_format(Format::new(3, "de-DE", true));
+        assert_eq!(evaluate_in(&ctx, "1234.5*2").unwrap().unwrap().display, "2.469");
+        assert_eq!(evaluate_in(&ctx, "1234.5/2").unwrap().unwrap().display, "617,25");
+        assert_eq!(evaluate_in(&ctx, "2^20").unwrap().unwrap().display, "1.048.576");
+
+        let ctx = Context::with_format(Format::new(2, "de-CH", true));
+        assert_eq!(evaluate_in(&ctx, "10^6").unwrap().unwrap().display, "1'000'000");
+
+        let ctx = Context::with_format(Format::new(2, "fr", false));
+        assert_eq!(evaluate_in(&ctx, "10^6+0.5").unwrap().unwrap().display, "1000000,5");
+    }
+
+    #[test]
+    fn error_kinds_and_positions() {
+        assert_eq!(error("1/0"), Some((ErrorKind::DivisionByZero, 1)));
+        assert_eq!(error("2 +"), Some((ErrorKind::Incomplete, 3)));
+        assert_eq!(error("(2+3"), Some((ErrorKind::UnclosedParen, 0)));
+        assert_eq!(error("2 * (1 + 3))"), Some((ErrorKind::UnexpectedChar, 11)));
+        assert_eq!(error("2 + * 3"), Some((ErrorKind::UnexpectedChar, 4)));
+        assert_eq!(error("1.2.3 + 1"), Some((ErrorKind::UnexpectedChar, 0)));
+        assert_eq!(error("ln(0) + 1"), Some((ErrorKind::Undefined, 0)));
+        assert_eq!(error("2 * (1 + ln(0))"), Some((ErrorKind::Undefined, 9)));
+        assert_eq!(error("exp(800) * 0"), Some((ErrorKind::Undefined, 0)));
+        assert_eq!(error("2 * exp(800)^2"), Some((ErrorKind::Undefined, 4)));
+        assert_eq!(error("sqrt + 1"), Some((ErrorKind::UnknownIdentifier, 0)));
+    }
+
+    #[test]
+    fn error_positions_in_assignment() {
+        let ctx = Context::default();
+        let err = evaluate_in(&ctx, "x = 4 / 0").unwrap().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::DivisionByZero);
+        assert_eq!(err.position, 6);
+        assert_eq!(err.to_string(), "Division by zero at column 7");
+
+        let err = evaluate_in(&ctx, "y = 1 + ln(0)").unwrap().unwrap_err();
+        assert_eq!((err.kind, err.position), (ErrorKind::Undefined, 8));
+    }
+
+    #[test]
+    fn plain_words_are_not_errors() {
+        assert_eq!(error("windows 10 - pro"), None);
+        assert!(evaluate_in(&Context::default(), "windows 10 - pro").is_none());
+        assert!(evaluate_in(&Context::default(), "foo-bar").is_none());
+        assert!(evaluate_in(&Context::default(), "c++").is_none());
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        assert_eq!(evaluate("2(3+4)"), Some("14".into()));
+        assert_eq!(evaluate("(1+1)(2+3)"), Some("10".into()));
+        assert_eq!(evaluate("3pi"), Some("9.4247779608".into()));
+        assert_eq!(evaluate("2 sqrt(9)"), Some("6".into()));
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(evaluate("15% of 80"), Some("12".into()));
+        assert_eq!(evaluate("80 + 15%"), Some("92".into()));
+        assert_eq!(evaluate("80 - 25%"), Some("60".into()));
+        assert_eq!(evaluate("10 % off 250"), Some("225".into()));
+        assert_eq!(evaluate("200 * 10%"), Some("20".into()));
+        assert_eq!(evaluate("50%"), Some("0.5".into()));
+    }
+
+    #[test]
+    fn multiplication_symbols() {
+        assert_eq!(evaluate("3 × 4"), Some("12".into()));
+        assert_eq!(evaluate("12 ÷ 4"), Some("3".into()));
+        assert_eq!(evaluate("3x4"), Some("12".into()));
+        assert_eq!(evaluate("3 x 4"), Some("12".into()));
+        assert_eq!(evaluate("(1+2) x 3"), Some("9".into()));
+    }
+
+    #[test]
+    fn x_is_still_a_variable() {
+        let mut ctx = Context::default();
+        ctx.commit(&evaluate_in(&ctx, "x = 5").unwrap().unwrap());
+        assert_eq!(evaluate_in(&ctx, "2x").unwrap().unwrap().display, "10");
+        assert_eq!(evaluate_in(&ctx, "x * 3").unwrap().unwrap().display, "15");
+    }
+
+    #[test]
+    fn comma_decimals() {
+        assert_eq!(evaluate("3,5 + 1"), Some("4.5".into()));
+        assert_eq!(evaluate("1,000 + 1"), Some("1001".into()));
+        assert_eq!(evaluate("1,234.5 * 2"), Some("2469".into()));
+        assert_eq!(evaluate("1.234,5 * 2"), Some("2469".into()));
+
+        let ctx = Context::with_format(Format::new(10, "de", false));
+        assert_eq!(evaluate_in(&ctx, "1,5 * 2").unwrap().unwrap().display, "3");
+        assert_eq!(evaluate_in(&ctx, "1.500 * 2").unwrap().unwrap().display, "3000");
+        assert_eq!(evaluate_in(&ctx, "1,000 * 2").unwrap().unwrap().display, "2");
+    }
+
+    #[test]
+    fn whitespace_separates_numbers() {
+        assert_eq!(error("2 3 + 1"), Some((ErrorKind::UnexpectedChar, 2)));
+    }
 }
@@ -0,0 +1,186 @@
+//! Command-line interface. Subcommands started while Cheru is already running
+//! are forwarded to that instance.
+
+pub const USAGE: &str = "Usage: cheru [COMMAND]
+
+Commands:
+  toggle                Show or hide the launcher
+  show [--query TEXT]   Show the launcher, optionally with TEXT typed in
+  reindex               Rescan installed applications
+  quit                  Quit the running instance
+  search QUERY [--type apps|folders|images|files] [--json]
+                        Print ranked results without starting the app
+
+Options:
+  --check-config        Validate config.toml and exit
+  -h, --help            Print this help
+  -V, --version         Print the version
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// No subcommand: start Cheru, or show the running instance
+    Run,
+    Toggle,
+    Show {
+        query: Option<String>,
+    },
+    Reindex,
+    Quit,
+    Search {
+        query: String,
+        kind: Option<SearchKind>,
+        json: bool,
+    },
+    CheckConfig,
+    Help,
+    Version,
+}
+
+/// Which index `cheru search --type` looks in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Apps,
+    Folders,
+    Images,
+    /// File contents, via ripgrep
+    Files,
+}
+
+/// Parse arguments, excluding the program name.
+pub fn parse<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    let Some(first) = args.next() else {
+        return Ok(Command::Run);
+    };
+
+    let command = match first.as_str() {
+        "toggle" => Command::Toggle,
+        "reindex" => Command::Reindex,
+        "quit" => Command::Quit,
+        "--check-config" => Command::CheckConfig,
+        "-h" | "--help" | "help" => Command::Help,
+        "-V" | "--version" => Command::Version,
+        "show" => {
+            let mut query = None;
+            while let Some(arg) = args.next() {
+                if let Some(value) = arg.strip_prefix("--query=") {
+                    query = Some(value.to_string());
+                } else if arg == "--query" || arg == "-q" {
+                    query = Some(args.next().ok_or("--query needs a value")?);
+                } else {
+                    return Err(format!("unexpected argument `{}` for `show`", arg));
+                }
+            }
+            return Ok(Command::Show { query });
+        }
+        "search" => {
+            let mut query: Option<String> = None;
+            let mut kind = None;
+            let mut json = false;
+            while let Some(arg) = args.next() {
+                let type_value = match arg.strip_prefix("--type=") {
+                    Some(value) => Some(value.to_string()),
+                    None if arg == "--type" || arg == "-t" => {
+                        Some(args.next().ok_or("--type needs a value")?)
+                    }
+                    None => None,
+                };
+                if let Some(value) = type_value {
+                    kind = Some(match value.as_str() {
+                        "apps" => SearchKind::Apps,
+                        "folders" => SearchKind::Folders,
+                        "images" => SearchKind::Images,
+                        "files" => SearchKind::Files,
+                        other => {
+                            return Err(format!(
+                                "unknown type `{}`, expected apps, folders, images or files",
+                                other
+                            ))
+                        }
+                    });
+                } else if arg == "--json" {
+                    json = true;
+                } else if arg.starts_with('-') && arg.len() > 1 {
+                    return Err(format!("unexpected option `{}` for `search`", arg));
+                } else {
+                    // Unquoted words form one query, as typed into the launcher
+                    query = Some(match query {
+                        Some(q) => format!("{} {}", q, arg),
+                        None => arg,
+                    });
+                }
+            }
+            let query = query.ok_or("`search` needs a query")?;
+            return Ok(Command::Search { query, kind, json });
+        }
+        other => return Err(format!("unknown command `{}`", other)),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("unexpected argument `{}`", extra)),
+        None => Ok(command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_subcommands() {
+        assert_eq!(parse_str(&[]), Ok(Command::Run));
+        assert_eq!(parse_str(&["toggle"]), Ok(Command::Toggle));
+        assert_eq!(parse_str(&["reindex"]), Ok(Command::Reindex));
+        assert_eq!(parse_str(&["--check-config"]), Ok(Command::CheckConfig));
+        assert!(parse_str(&["toggle", "now"]).is_err());
+        assert!(parse_str(&["open"]).is_err());
+    }
+
+    #[test]
+    fn test_show_query() {
+        assert_eq!(parse_str(&["show"]), Ok(Command::Show { query: None }));
+        assert_eq!(
+            parse_str(&["show", "--query", "downloads/"]),
+            Ok(Command::Show {
+                query: Some("downloads/".to_string())
+            })
+        );
+        assert_eq!(
+            parse_str(&["show", "--query=> todo"]),
+            Ok(Command::Show {
+                query: Some("> todo".to_string())
+            })
+        );
+        assert!(parse_str(&["show", "--query"]).is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        assert_eq!(
+            parse_str(&["search", "visual", "studio", "--json"]),
+            Ok(Command::Search {
+                query: "visual studio".to_string(),
+                kind: None,
+                json: true
+            })
+        );
+        assert_eq!(
+            parse_str(&["search", "--type", "folders", "proj"]),
+            Ok(Command::Search {
+                query: "proj".to_string(),
+                kind: Some(SearchKind::Folders),
+                json: false
+            })
+        );
+        assert!(parse_str(&["search"]).is_err());
+        assert!(parse_str(&["search", "x", "--type=music"]).is_err());
+    }
+}
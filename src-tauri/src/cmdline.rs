@@ -0,0 +1,109 @@
+//! Command lines typed after `!`, split and quoted the way a POSIX shell
+//! would, without any expansion.
+
+/// Split `line` into words. Single quotes keep everything, double quotes
+/// allow `\"`, `\\`, `\$` and `` \` `` escapes, and a backslash outside
+/// quotes escapes the next character. An unclosed quote runs to the end.
+pub fn split(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                for c in chars.by_ref() {
+                    if c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                }
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                            word.extend(chars.next());
+                        }
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Quote `arg` so `split` reads it back as one word.
+pub fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Join words into a line `split` turns back into the same words.
+pub fn join<S: AsRef<str>>(words: &[S]) -> String {
+    words
+        .iter()
+        .map(|w| quote(w.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("rsync -av a b"), ["rsync", "-av", "a", "b"]);
+        assert_eq!(split("  htop  "), ["htop"]);
+        assert_eq!(
+            split(r#"grep -r 'it''s' "a \"b\" \n" c\ d"#),
+            ["grep", "-r", "its", r#"a "b" \n"#, "c d"]
+        );
+        assert_eq!(split("echo '' \"unclosed x"), ["echo", "", "unclosed x"]);
+        assert!(split("   ").is_empty());
+    }
+
+    #[test]
+    fn test_join_round_trips() {
+        let words = [
+            "/usr/bin/my tool",
+            "--name=it's",
+            "",
+            "$HOME",
+            "a\\b",
+            "-av",
+        ];
+        let line = join(&words);
+        assert_eq!(split(&line), words);
+        assert_eq!(join(&["ls", "-la", "/tmp"]), "ls -la /tmp");
+    }
+}
@@ -1,20 +1,73 @@
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Mutex, OnceLock, RwLock};
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 use serde::Serialize;
+use tauri::ipc::Channel;
 use tauri::{AppHandle, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_shell::ShellExt;
 use thiserror::Error;
 
+use crate::actions::{self, Action, ActionKind, ActionTarget};
+use crate::allowlist;
+use crate::calc_history::{self, HistoryEntry};
+use crate::calculator;
+use crate::cmdline;
 use crate::config;
+use crate::fileops::{self, FileOpError};
 use crate::indexer::{AppEntry, ResultType};
 use crate::matcher::FuzzyMatcher;
+use crate::paths;
+use crate::scripts::{self, Script};
+use crate::shell::{self, ShellEvent, Stream};
+use crate::themes;
+use crate::web;
+#[cfg(target_os = "linux")]
+use crate::{mime, open_with};
 
 pub struct AppState {
     pub index: RwLock<Vec<AppEntry>>,
-    pub folder_index: OnceLock<Vec<AppEntry>>,
-    pub image_index: OnceLock<Vec<AppEntry>>,
+    /// Folders and images, listed the first time they are searched and kept
+    /// in step with file operations
+    pub folder_index: RwLock<Option<Vec<AppEntry>>>,
+    pub image_index: RwLock<Option<Vec<AppEntry>>>,
+    /// `$PATH` executables, listed the first time commands are searched and
+    /// again on reindex
+    pub command_index: RwLock<Option<Vec<AppEntry>>>,
+    /// Output of the latest `$` command
+    pub shell: Mutex<shell::Capture>,
     pub matcher: Mutex<FuzzyMatcher>,
+    pub calc: Mutex<calculator::Context>,
+    /// Effective config, kept current by the config file watcher
+    pub config: RwLock<config::Config>,
+    /// Problems found the last time config.toml was loaded
+    pub config_diagnostics: RwLock<Vec<config::Diagnostic>>,
+    /// Script commands, re-read as their files change
+    pub scripts: Mutex<scripts::Cache>,
+    /// shared-mime-info, read the first time a file's type is needed
+    #[cfg(target_os = "linux")]
+    pub mime_db: OnceLock<mime::MimeDb>,
+    /// What the Open With picker offers for the file it was last opened on
+    #[cfg(target_os = "linux")]
+    pub open_with: Mutex<OpenWithChoices>,
+}
+
+/// Open With choices for one file, worked out when the picker opens and
+/// then only filtered.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+pub struct OpenWithChoices {
+    path: PathBuf,
+    mime: String,
+    apps: Vec<crate::indexer::DesktopApp>,
+    /// The file type's handlers, best first, then every other listed app
+    entries: Vec<AppEntry>,
+    /// How many of `entries` are handlers
+    handlers: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -44,6 +97,20 @@ pub enum CommandError {
     LaunchError(String),
     #[error("Window error: {0}")]
     WindowError(String),
+    #[error("Calculation error: {0}")]
+    CalcError(String),
+    #[error("Clipboard error: {0}")]
+    ClipboardError(String),
+    #[error("Config error: {0}")]
+    ConfigError(String),
+    #[error("{0}")]
+    FileError(#[from] FileOpError),
+    #[error("Commands are off; set [commands] enabled = true in config.toml")]
+    CommandsDisabled,
+    #[error("Shell commands are off; set [commands] shell = true in config.toml")]
+    ShellDisabled,
+    #[error("No script named {0}")]
+    ScriptNotFound(String),
 }
 
 impl Serialize for CommandError {
@@ -55,23 +122,60 @@ impl Serialize for CommandError {
     }
 }
 
-const MAX_RESULTS: usize = 50;
+pub(crate) const MAX_RESULTS: usize = 50;
+pub(crate) const MAX_FOLDER_RESULTS: usize = 10;
+pub(crate) const MAX_IMAGE_RESULTS: usize = 20;
+pub(crate) const MAX_COMMAND_RESULTS: usize = 10;
+/// Folder and image search start at this many bytes of query
+pub(crate) const MIN_PATH_QUERY_LEN: usize = 2;
+
+/// Fuzzy-match `query` against `entries`, best first.
+pub(crate) fn rank(
+    matcher: &mut FuzzyMatcher,
+    query: &str,
+    entries: &[AppEntry],
+    limit: usize,
+) -> Vec<AppResult> {
+    matcher
+        .search(query, entries)
+        .into_iter()
+        .take(limit)
+        .map(|idx| AppResult::from(&entries[idx]))
+        .collect()
+}
+
+/// Rank `query` against `index`, which `build` fills on first use.
+fn rank_lazy(
+    state: &AppState,
+    index: &RwLock<Option<Vec<AppEntry>>>,
+    build: fn() -> Vec<AppEntry>,
+    query: &str,
+    limit: usize,
+) -> Vec<AppResult> {
+    if index.read().unwrap_or_else(|e| e.into_inner()).is_none() {
+        let mut index = index.write().unwrap_or_else(|e| e.into_inner());
+        if index.is_none() {
+            *index = Some(build());
+        }
+    }
+    let index = index.read().unwrap_or_else(|e| e.into_inner());
+    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+    rank(&mut matcher, query, index.as_deref().unwrap_or_default(), limit)
+}
 
 #[tauri::command]
 pub fn search_apps(query: String, state: State<'_, AppState>) -> Vec<AppResult> {
     let index = state.index.read().unwrap_or_else(|e| e.into_inner());
     let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
-    let indices = matcher.search(&query, &index);
-
-    indices
-        .into_iter()
-        .take(MAX_RESULTS)
-        .map(|idx| AppResult::from(&index[idx]))
-        .collect()
+    rank(&mut matcher, &query, &index, MAX_RESULTS)
 }
 
-fn validate_exec_path(exec: &str) -> Result<(), CommandError> {
-    let path = std::path::Path::new(exec);
+/// Resolve the program an exec line starts with, through `$PATH` for bare
+/// names. Returns the path to run, which may be a symlink, and the file it
+/// resolves to.
+fn resolve_program(program: &str) -> Result<(PathBuf, PathBuf), CommandError> {
+    let path = actions::find_program(program)
+        .ok_or_else(|| CommandError::LaunchError(format!("Cannot find {} in PATH", program)))?;
 
     // Must be an absolute path
     if !path.is_absolute() {
@@ -83,65 +187,362 @@ fn validate_exec_path(exec: &str) -> Result<(), CommandError> {
         CommandError::LaunchError(format!("Cannot resolve path: {}", e))
     })?;
 
-    let allowed_prefixes = [
-        "/Applications",
-        "/System/Applications",
-        "/usr/bin",
-        "/usr/local/bin",
-        "/opt",
-    ];
-
-    // Also allow home directory Applications
-    let home_apps = dirs::home_dir().map(|h| h.join("Applications"));
-
-    let canonical_str = canonical.to_string_lossy();
+    Ok((path, canonical))
+}
 
-    let is_allowed = allowed_prefixes.iter().any(|prefix| canonical_str.starts_with(prefix))
-        || home_apps.as_ref().map_or(false, |h| canonical_str.starts_with(&h.to_string_lossy().to_string()));
+/// Resolve `program` as `resolve_program` does and check it lives in a
+/// folder `[launch]` allows. Returns the path to run.
+fn validate_exec_path(program: &str, allowed: &[PathBuf]) -> Result<PathBuf, CommandError> {
+    let (path, canonical) = resolve_program(program)?;
 
-    if !is_allowed {
+    if !allowlist::is_allowed(&canonical, allowed) {
         return Err(CommandError::LaunchError(format!(
-            "Path not in allowed locations: {}",
-            canonical_str
+            "Path not in allowed locations: {} (see [launch] allowed_paths)",
+            canonical.display()
         )));
     }
 
-    Ok(())
+    Ok(path)
+}
+
+/// Check an exec line's program with `validate_exec_path`, and for
+/// `env [VAR=value…] program …` the program env runs as well. Shells and
+/// other interpreters in allowed folders still run whatever they're given.
+fn validate_exec<S: AsRef<str>>(parts: &[S], allowed: &[PathBuf]) -> Result<PathBuf, CommandError> {
+    let (program, rest) = parts
+        .split_first()
+        .ok_or_else(|| CommandError::LaunchError("Empty exec command".to_string()))?;
+    let path = validate_exec_path(program.as_ref(), allowed)?;
+    if path.file_name().is_some_and(|name| name == "env") {
+        let target = rest
+            .iter()
+            .map(AsRef::as_ref)
+            .find(|word| !word.contains('=') && !word.starts_with('-'));
+        if let Some(target) = target {
+            validate_exec_path(target, allowed)?;
+        }
+    }
+    Ok(path)
+}
+
+/// Folders launched programs may live in, from `[launch]`.
+fn allowed_dirs(state: &AppState) -> Vec<PathBuf> {
+    let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+    config.launch.allowed_dirs()
 }
 
 #[tauri::command]
-pub fn launch_app(exec: String) -> Result<(), CommandError> {
-    let exec = strip_field_codes(&exec);
+pub fn launch_app(exec: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    launch_with(&exec, &[], false, &allowed_dirs(&state))
+}
+
+/// Launch an app's exec string, passing `paths` as extra arguments.
+/// `new_instance` starts another copy on macOS even if one is running.
+fn launch_with(
+    exec: &str,
+    paths: &[&Path],
+    new_instance: bool,
+    allowed: &[PathBuf],
+) -> Result<(), CommandError> {
+    let exec = strip_field_codes(exec);
 
     // Validate the executable path
     #[cfg(target_os = "macos")]
     {
         if exec.ends_with(".app") || exec.contains(".app/") {
-            validate_exec_path(&exec)?;
-            Command::new("open")
-                .arg("-a")
+            validate_exec_path(&exec, allowed)?;
+            let mut open = Command::new("open");
+            if new_instance {
+                open.arg("-n");
+            }
+            open.arg("-a")
                 .arg(&exec)
+                .args(paths)
                 .spawn()
                 .map_err(|e| CommandError::LaunchError(e.to_string()))?;
             return Ok(());
         }
     }
+    // Elsewhere the app decides whether a second launch opens a new window
+    let _ = new_instance;
 
     let parts: Vec<&str> = exec.split_whitespace().collect();
-    if parts.is_empty() {
-        return Err(CommandError::LaunchError("Empty exec command".to_string()));
-    }
-
-    validate_exec_path(parts[0])?;
+    let program = validate_exec(&parts, allowed)?;
 
-    Command::new(parts[0])
+    Command::new(program)
         .args(&parts[1..])
+        .args(paths)
         .spawn()
         .map_err(|e| CommandError::LaunchError(e.to_string()))?;
 
     Ok(())
 }
 
+/// `$PATH` executables matching the first word of `query`. The rest of the
+/// query is passed to each as arguments; an absolute path is run as typed.
+#[tauri::command]
+pub fn search_commands(
+    query: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<AppResult>, CommandError> {
+    if !commands_config(&state).enabled {
+        return Err(CommandError::CommandsDisabled);
+    }
+    let words = cmdline::split(&query);
+    let Some((program, args)) = words.split_first() else {
+        return Ok(Vec::new());
+    };
+    if program.starts_with('/') {
+        return Ok(vec![AppResult {
+            name: cmdline::join(&words),
+            exec: cmdline::join(&words),
+            icon: None,
+            description: None,
+            result_type: ResultType::Command,
+        }]);
+    }
+
+    let mut results = rank_lazy(
+        &state,
+        &state.command_index,
+        crate::indexer::build_command_index,
+        program,
+        MAX_COMMAND_RESULTS,
+    );
+    for result in &mut results {
+        let mut line = vec![result.exec.clone()];
+        line.extend(args.iter().cloned());
+        result.exec = cmdline::join(&line);
+        if !args.is_empty() {
+            result.name = format!("{} {}", result.name, cmdline::join(args));
+        }
+    }
+    Ok(results)
+}
+
+/// Outcome of asking to run a command.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum RunOutcome {
+    Started,
+    /// The program is outside the `[launch]` folders; run again with
+    /// `confirmed` once the user agrees
+    Confirm {
+        path: String,
+    },
+}
+
+/// Run a command line as `search_commands` built it, in a terminal if
+/// `terminal` says so or, when unset, `[commands] in_terminal` does.
+#[tauri::command]
+pub fn run_command(
+    command: String,
+    terminal: Option<bool>,
+    confirmed: bool,
+    state: State<'_, AppState>,
+) -> Result<RunOutcome, CommandError> {
+    let config = commands_config(&state);
+    if !config.enabled {
+        return Err(CommandError::CommandsDisabled);
+    }
+    let words = cmdline::split(&command);
+    let Some((program, args)) = words.split_first() else {
+        return Err(CommandError::LaunchError("Empty command".to_string()));
+    };
+
+    let (path, canonical) = resolve_program(program)?;
+    if !confirmed && !allowlist::is_allowed(&canonical, &allowed_dirs(&state)) {
+        return Ok(RunOutcome::Confirm {
+            path: canonical.to_string_lossy().to_string(),
+        });
+    }
+
+    let started = if terminal.unwrap_or(config.in_terminal) {
+        actions::run_in_terminal(&path, args, config.terminal.as_deref())
+    } else {
+        Command::new(&path)
+            .args(args)
+            .current_dir(dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")))
+            .spawn()
+            .map(drop)
+    };
+    started.map_err(|e| CommandError::LaunchError(e.to_string()))?;
+    Ok(RunOutcome::Started)
+}
+
+/// Run `command` with the user's shell from the home folder, for `$` mode.
+/// Output lines and then the exit status arrive on `on_output`.
+#[tauri::command]
+pub fn run_shell(
+    command: String,
+    on_output: Channel<ShellEvent>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let config = commands_config(&state);
+    if !config.shell {
+        return Err(CommandError::ShellDisabled);
+    }
+    if command.trim().is_empty() {
+        return Err(CommandError::LaunchError("Empty command".to_string()));
+    }
+
+    let run = {
+        let mut capture = state.shell.lock().unwrap_or_else(|e| e.into_inner());
+        capture.run += 1;
+        capture.command = command.clone();
+        capture.output.clear();
+        capture.run
+    };
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    shell::spawn(
+        &shell::user_shell(),
+        &command,
+        &home,
+        Duration::from_secs(config.shell_timeout),
+        move |event| {
+            if let ShellEvent::Line { line, .. } = &event {
+                let state = app.state::<AppState>();
+                let mut capture = state.shell.lock().unwrap_or_else(|e| e.into_inner());
+                // A newer run has started
+                if capture.run == run {
+                    capture.output.push_str(line);
+                    capture.output.push('\n');
+                }
+            }
+            let _ = on_output.send(event);
+        },
+    )
+    .map_err(|e| CommandError::LaunchError(e.to_string()))
+}
+
+/// Script commands matching `query`, or all of them when it's empty.
+#[tauri::command]
+pub fn search_scripts(query: String, state: State<'_, AppState>) -> Vec<AppResult> {
+    let entries: Vec<AppEntry> = {
+        let mut cache = state.scripts.lock().unwrap_or_else(|e| e.into_inner());
+        cache.refresh();
+        cache
+            .scripts()
+            .into_iter()
+            .map(|script| AppEntry {
+                name: script.title.clone(),
+                exec: format!("script:{}", script.name),
+                icon: script.icon.clone(),
+                description: script.description.clone(),
+                result_type: ResultType::Script,
+            })
+            .collect()
+    };
+    if query.trim().is_empty() {
+        return entries.iter().map(AppResult::from).collect();
+    }
+    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+    rank(&mut matcher, &query, &entries, MAX_RESULTS)
+}
+
+/// The script command whose file is `name`, for its argument prompt.
+#[tauri::command]
+pub fn get_script(name: String, state: State<'_, AppState>) -> Result<Script, CommandError> {
+    find_script(&state, name)
+}
+
+fn find_script(state: &AppState, name: String) -> Result<Script, CommandError> {
+    let mut cache = state.scripts.lock().unwrap_or_else(|e| e.into_inner());
+    cache.refresh();
+    cache
+        .find(&name)
+        .cloned()
+        .ok_or(CommandError::ScriptNotFound(name))
+}
+
+/// Scripts left out because their header is broken, reported like
+/// config.toml's problems.
+#[tauri::command]
+pub fn get_script_diagnostics(state: State<'_, AppState>) -> Vec<ConfigReport> {
+    let mut cache = state.scripts.lock().unwrap_or_else(|e| e.into_inner());
+    cache.refresh();
+    cache
+        .problems()
+        .into_iter()
+        .map(|(path, diagnostic)| ConfigReport {
+            path: path.to_string_lossy().to_string(),
+            diagnostics: vec![diagnostic.clone()],
+        })
+        .collect()
+}
+
+/// Run a script command with `args`, split like a shell would, from the
+/// home folder. Output reaches `on_output` only for inline scripts;
+/// notification and clipboard scripts report once they end.
+#[tauri::command]
+pub fn run_script(
+    name: String,
+    args: String,
+    on_output: Channel<ShellEvent>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<scripts::Mode, CommandError> {
+    let script = find_script(&state, name)?;
+    let args = cmdline::split(&args);
+    script
+        .check_arguments(&args)
+        .map_err(CommandError::LaunchError)?;
+
+    let mut cmd = Command::new(&script.path);
+    cmd.args(&args)
+        .current_dir(dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")));
+    let (mode, title) = (script.mode, script.title);
+    let stdout = Mutex::new(String::new());
+    let last_line = Mutex::new(None::<String>);
+    shell::spawn_command(cmd, script.timeout, move |event| {
+        match &event {
+            ShellEvent::Line { stream, line } => {
+                if *stream == Stream::Stdout {
+                    let mut stdout = stdout.lock().unwrap_or_else(|e| e.into_inner());
+                    stdout.push_str(line);
+                    stdout.push('\n');
+                }
+                if !line.trim().is_empty() {
+                    *last_line.lock().unwrap_or_else(|e| e.into_inner()) = Some(line.clone());
+                }
+            }
+            ShellEvent::Exit {
+                code, timed_out, ..
+            } => match mode {
+                scripts::Mode::Notification => {
+                    let last_line = last_line.lock().unwrap_or_else(|e| e.into_inner());
+                    let body = match (*timed_out, *code, last_line.as_deref()) {
+                        (true, _, _) => "Timed out".to_string(),
+                        (_, _, Some(line)) => line.to_string(),
+                        (_, Some(0), None) => "Done".to_string(),
+                        (_, Some(code), None) => format!("Exited with {}", code),
+                        (_, None, None) => "Killed".to_string(),
+                    };
+                    let _ = actions::notify(&title, &body);
+                }
+                scripts::Mode::Clipboard => {
+                    let stdout = stdout.lock().unwrap_or_else(|e| e.into_inner());
+                    if !stdout.is_empty() {
+                        let _ = app.clipboard().write_text(stdout.trim_end().to_string());
+                    }
+                }
+                scripts::Mode::Silent | scripts::Mode::Inline => {}
+            },
+        }
+        if mode == scripts::Mode::Inline {
+            let _ = on_output.send(event);
+        }
+    })
+    .map_err(|e| CommandError::LaunchError(e.to_string()))?;
+    Ok(mode)
+}
+
+fn commands_config(state: &AppState) -> config::CommandsConfig {
+    let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+    config.commands.clone()
+}
+
 #[tauri::command]
 pub fn hide_launcher_window(app: AppHandle) -> Result<(), CommandError> {
     if let Some(window) = app.get_webview_window("launcher") {
@@ -158,17 +559,177 @@ pub fn get_index_size(state: State<'_, AppState>) -> usize {
 }
 
 #[tauri::command]
-pub fn get_theme() -> config::ThemeConfig {
-    let cfg = config::load();
-    config::ThemeConfig {
-        theme: cfg.theme,
-        colors: cfg.colors,
+pub fn get_theme(state: State<'_, AppState>) -> themes::ThemeConfig {
+    state
+        .config
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .theme_config()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigReport {
+    pub path: String,
+    pub diagnostics: Vec<config::Diagnostic>,
+}
+
+#[tauri::command]
+pub fn get_config_diagnostics(state: State<'_, AppState>) -> ConfigReport {
+    ConfigReport {
+        path: paths::config_file().to_string_lossy().to_string(),
+        diagnostics: state
+            .config_diagnostics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone(),
+    }
+}
+
+#[tauri::command]
+pub fn get_config(state: State<'_, AppState>) -> config::Config {
+    state
+        .config
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Update one config key (e.g. `theme`, `calculator.precision`) in
+/// config.toml. The config watcher applies it, as with any other edit.
+#[tauri::command]
+pub fn set_config_value(key: String, value: serde_json::Value) -> Result<(), CommandError> {
+    config::set_value(&key, &value).map_err(CommandError::ConfigError)
+}
+
+/// Choices for a launcher settings entry such as "Cheru: Change Theme".
+/// Each row's exec is `config-set:<key>=<json value>`.
+#[tauri::command]
+pub fn get_setting_choices(
+    key: String,
+    filter: String,
+    state: State<'_, AppState>,
+) -> Vec<AppResult> {
+    let current = {
+        let cfg = state.config.read().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_value(&*cfg)
+            .ok()
+            .and_then(|v| v.pointer(&format!("/{}", key.replace('.', "/"))).cloned())
+    };
+
+    let entries: Vec<AppEntry> = config::choices(&key)
+        .into_iter()
+        .map(|value| AppEntry {
+            name: match &value {
+                serde_json::Value::Bool(true) => "On".to_string(),
+                serde_json::Value::Bool(false) => "Off".to_string(),
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            },
+            exec: format!("config-set:{}={}", key, value),
+            icon: None,
+            description: (current.as_ref() == Some(&value)).then(|| "Current".to_string()),
+            result_type: ResultType::System,
+        })
+        .collect();
+
+    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+    matcher
+        .search(&filter, &entries)
+        .into_iter()
+        .map(|idx| AppResult::from(&entries[idx]))
+        .collect()
+}
+
+/// Outcome of evaluating a query that looks like math.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum CalcOutcome {
+    Ok {
+        value: String,
+    },
+    Error {
+        kind: calculator::ErrorKind,
+        position: usize,
+        message: String,
+    },
+}
+
+/// Returns `None` for queries that are not math, so the frontend only shows
+/// an error hint when the user was clearly typing an expression.
+#[tauri::command]
+pub fn eval_expression(expr: String, state: State<'_, AppState>) -> Option<CalcOutcome> {
+    let ctx = state.calc.lock().unwrap_or_else(|e| e.into_inner());
+    let outcome = match calculator::evaluate_in(&ctx, &expr)? {
+        Ok(eval) => CalcOutcome::Ok {
+            value: eval.display,
+        },
+        Err(e) => CalcOutcome::Error {
+            kind: e.kind,
+            position: e.position,
+            message: e.to_string(),
+        },
+    };
+    Some(outcome)
+}
+
+#[tauri::command]
+pub fn eval_datetime(query: String) -> Option<String> {
+    crate::datetime::evaluate(&query)
+}
+
+/// Commit a calculation: bind `ans` (and any assigned variable), record it in
+/// the history and copy the value to the clipboard.
+#[tauri::command]
+pub fn commit_calculation(
+    expr: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let eval = {
+        let mut ctx = state.calc.lock().unwrap_or_else(|e| e.into_inner());
+        let eval = calculator::evaluate_in(&ctx, &expr)
+            .ok_or_else(|| CommandError::CalcError(format!("Not an expression: {}", expr)))?
+            .map_err(|e| CommandError::CalcError(e.to_string()))?;
+        ctx.commit(&eval);
+        eval
+    };
+
+    if let Err(e) = calc_history::append(HistoryEntry::new(&expr, &eval.display)) {
+        eprintln!("Warning: failed to save calculator history: {}", e);
     }
+
+    copy_to_clipboard(eval.display.clone(), app)?;
+    Ok(eval.display)
+}
+
+#[tauri::command]
+pub fn get_calc_history(filter: String, state: State<'_, AppState>) -> Vec<AppResult> {
+    let entries: Vec<AppEntry> = calc_history::load()
+        .into_iter()
+        .map(|h| AppEntry {
+            name: format!("{} = {}", h.expression, h.result),
+            exec: format!("calc-history:{}", h.result),
+            icon: None,
+            description: None,
+            result_type: ResultType::Calculator,
+        })
+        .collect();
+
+    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+    let indices = matcher.search(&filter, &entries);
+
+    indices
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|idx| AppResult::from(&entries[idx]))
+        .collect()
 }
 
 #[tauri::command]
-pub fn eval_expression(expr: String) -> Option<String> {
-    crate::calculator::evaluate(&expr)
+pub fn copy_to_clipboard(text: String, app: AppHandle) -> Result<(), CommandError> {
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| CommandError::ClipboardError(e.to_string()))
 }
 
 #[tauri::command]
@@ -262,10 +823,116 @@ pub fn run_system_command(id: String) -> Result<(), CommandError> {
     Ok(())
 }
 
+/// Quicklinks for `query`: a search when it starts with a quicklink's
+/// keyword, then quicklinks by name, the one whose keyword it is first.
+#[tauri::command]
+pub fn search_quicklinks(query: String, state: State<'_, AppState>) -> Vec<AppResult> {
+    let quicklinks = {
+        let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+        config.quicklinks.clone()
+    };
+    let mut results = Vec::new();
+    if let Some((link, terms)) = web::keyword_search(&quicklinks, &query) {
+        results.push(web_search(&link.name, &link.url, terms));
+    }
+    let query = query.trim();
+    if query.is_empty() {
+        return results;
+    }
+
+    let entries: Vec<AppEntry> = quicklinks
+        .iter()
+        .map(|link| {
+            let (exec, description, result_type) = if web::takes_query(&link.url) {
+                (
+                    format!("quicklink:{}", link.keyword),
+                    format!("{} <query>", link.keyword),
+                    ResultType::Quicklink,
+                )
+            } else {
+                (link.url.clone(), link.url.clone(), ResultType::WebSearch)
+            };
+            AppEntry {
+                name: link.name.clone(),
+                exec,
+                icon: None,
+                description: Some(description),
+                result_type,
+            }
+        })
+        .collect();
+    let mut ranked = {
+        let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+        rank(&mut matcher, query, &entries, MAX_RESULTS)
+    };
+    if let Some(i) = quicklinks
+        .iter()
+        .position(|link| link.keyword.eq_ignore_ascii_case(query))
+    {
+        ranked.retain(|r| r.exec != entries[i].exec);
+        ranked.insert(0, AppResult::from(&entries[i]));
+    }
+    results.extend(ranked);
+    results
+}
+
+/// The `[web] fallback` engine's search for `query`, offered when little
+/// else matches.
 #[tauri::command]
-pub fn open_url(url: String) -> Result<(), CommandError> {
-    if !url.starts_with("https://") {
-        return Err(CommandError::LaunchError("Only HTTPS URLs allowed".into()));
+pub fn web_fallback(query: String, state: State<'_, AppState>) -> Option<AppResult> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+    let (name, url) = web::fallback(&config.web, &config.quicklinks);
+    Some(web_search(&name, &url, query))
+}
+
+/// The first known `!bang` in `query`, as a search on its site, or the site
+/// itself when nothing else was typed.
+#[tauri::command]
+pub fn resolve_bang(query: String, state: State<'_, AppState>) -> Option<AppResult> {
+    let search = {
+        let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+        web::find_bang(&query, &config.bangs)?
+    };
+    let name = if search.terms.is_empty() {
+        format!("Open {}", search.site)
+    } else {
+        format!("Search {} for \"{}\"", search.site, search.terms)
+    };
+    Some(AppResult {
+        name,
+        exec: search.url,
+        icon: None,
+        description: Some("Open in browser".to_string()),
+        result_type: ResultType::WebSearch,
+    })
+}
+
+fn web_search(engine: &str, template: &str, terms: &str) -> AppResult {
+    AppResult {
+        name: format!("Search {} for \"{}\"", engine, terms),
+        exec: web::expand(template, terms),
+        icon: None,
+        description: Some("Open in browser".to_string()),
+        result_type: ResultType::WebSearch,
+    }
+}
+
+/// Open a URL whose scheme is in `[web] allowed_schemes`.
+#[tauri::command]
+pub fn open_url(url: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let allowed = {
+        let config = state.config.read().unwrap_or_else(|e| e.into_inner());
+        config.web.allowed_schemes.clone()
+    };
+    if !web::is_allowed(&url, &allowed) {
+        return Err(CommandError::LaunchError(format!(
+            "Only {} URLs are allowed; see [web] allowed_schemes",
+            allowed.join(", ")
+        )));
     }
 
     #[cfg(target_os = "macos")]
@@ -289,50 +956,41 @@ pub fn open_url(url: String) -> Result<(), CommandError> {
 
 #[tauri::command]
 pub fn search_folders(query: String, state: State<'_, AppState>) -> Vec<AppResult> {
-    if query.len() < 2 {
+    if query.len() < MIN_PATH_QUERY_LEN {
         return Vec::new();
     }
 
-    let folder_index = state.folder_index.get_or_init(|| {
-        crate::indexer::build_folder_index()
-    });
-
-    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
-    let indices = matcher.search(&query, folder_index);
-
-    indices
-        .into_iter()
-        .take(10)
-        .map(|idx| AppResult::from(&folder_index[idx]))
-        .collect()
+    rank_lazy(
+        &state,
+        &state.folder_index,
+        crate::indexer::build_folder_index,
+        &query,
+        MAX_FOLDER_RESULTS,
+    )
 }
 
 #[tauri::command]
 pub fn search_images(query: String, state: State<'_, AppState>) -> Vec<AppResult> {
-    if query.len() < 2 {
+    if query.len() < MIN_PATH_QUERY_LEN {
         return Vec::new();
     }
 
-    let image_index = state.image_index.get_or_init(|| {
-        crate::indexer::build_image_index()
-    });
-
-    let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
-    let indices = matcher.search(&query, image_index);
-
-    indices
-        .into_iter()
-        .take(20)
-        .map(|idx| AppResult::from(&image_index[idx]))
-        .collect()
+    rank_lazy(
+        &state,
+        &state.image_index,
+        crate::indexer::build_image_index,
+        &query,
+        MAX_IMAGE_RESULTS,
+    )
 }
 
 const MAX_CONTENT_RESULTS: usize = 20;
 
-#[tauri::command]
-pub async fn search_file_contents(query: String, app: AppHandle) -> Vec<AppResult> {
+/// ripgrep arguments for a content search of the usual project folders, or
+/// `None` if there is nothing to search.
+pub(crate) fn content_search_args(query: &str) -> Option<Vec<String>> {
     if query.len() < 2 {
-        return Vec::new();
+        return None;
     }
 
     let home = dirs::home_dir().unwrap_or_default();
@@ -346,7 +1004,7 @@ pub async fn search_file_contents(query: String, app: AppHandle) -> Vec<AppResul
     .collect();
 
     if search_dirs.is_empty() {
-        return Vec::new();
+        return None;
     }
 
     let mut args = vec![
@@ -360,21 +1018,14 @@ pub async fn search_file_contents(query: String, app: AppHandle) -> Vec<AppResul
         "--color".to_string(),
         "never".to_string(),
         "--no-heading".to_string(),
-        query,
+        query.to_string(),
     ];
     args.extend(search_dirs);
+    Some(args)
+}
 
-    let sidecar = match app.shell().sidecar("binaries/rg") {
-        Ok(cmd) => cmd,
-        Err(_) => return Vec::new(),
-    };
-
-    let output = match sidecar.args(&args).output().await {
-        Ok(o) => o,
-        Err(_) => return Vec::new(),
-    };
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// Turn ripgrep's `--files-with-matches` output into results.
+pub(crate) fn content_results(stdout: &str) -> Vec<AppResult> {
     let mut results = Vec::new();
 
     for line in stdout.lines() {
@@ -401,34 +1052,33 @@ pub async fn search_file_contents(query: String, app: AppHandle) -> Vec<AppResul
 }
 
 #[tauri::command]
-pub fn open_path(path: String) -> Result<(), CommandError> {
-    let p = std::path::Path::new(&path);
+pub async fn search_file_contents(query: String, app: AppHandle) -> Vec<AppResult> {
+    let Some(args) = content_search_args(&query) else {
+        return Vec::new();
+    };
 
-    // Must be absolute
-    if !p.is_absolute() {
-        return Err(CommandError::LaunchError("Path must be absolute".to_string()));
-    }
+    let sidecar = match app.shell().sidecar("binaries/rg") {
+        Ok(cmd) => cmd,
+        Err(_) => return Vec::new(),
+    };
 
-    // Must exist
-    if !p.exists() {
-        return Err(CommandError::LaunchError("Path does not exist".to_string()));
-    }
+    let output = match sidecar.args(&args).output().await {
+        Ok(o) => o,
+        Err(_) => return Vec::new(),
+    };
 
-    // Canonicalize to resolve symlinks
-    let canonical = p.canonicalize().map_err(|e| {
-        CommandError::LaunchError(format!("Cannot resolve path: {}", e))
-    })?;
+    content_results(&String::from_utf8_lossy(&output.stdout))
+}
 
-    // Must be under home directory (not system paths)
-    let home = dirs::home_dir().ok_or_else(|| {
-        CommandError::LaunchError("Cannot determine home directory".to_string())
-    })?;
+/// Resolve a path the frontend wants acted on. It must be absolute, exist
+/// and lie under the home directory once symlinks are resolved.
+fn validate_user_path(path: &str) -> Result<PathBuf, CommandError> {
+    Ok(fileops::under_home(Path::new(path))?)
+}
 
-    if !canonical.starts_with(&home) {
-        return Err(CommandError::LaunchError(
-            "Can only open paths under home directory".to_string(),
-        ));
-    }
+#[tauri::command]
+pub fn open_path(path: String) -> Result<(), CommandError> {
+    let canonical = validate_user_path(&path)?;
 
     #[cfg(target_os = "macos")]
     {
@@ -449,6 +1099,322 @@ pub fn open_path(path: String) -> Result<(), CommandError> {
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_actions(result: ActionTarget) -> Vec<Action> {
+    actions::for_result(&result.result_type)
+}
+
+/// Only apps from the index may be launched or acted on.
+fn ensure_indexed_app(state: &AppState, exec: &str) -> Result<(), CommandError> {
+    let index = state.index.read().unwrap_or_else(|e| e.into_inner());
+    if index
+        .iter()
+        .any(|e| e.exec == exec && e.result_type == ResultType::App)
+    {
+        Ok(())
+    } else {
+        Err(CommandError::LaunchError("Not an installed application".to_string()))
+    }
+}
+
+/// Run one of the actions `get_actions` offered for `result`. `with` is the
+/// app chosen for Open With, as `get_open_with_apps` listed it, the new name
+/// for Rename, or the folder for Move to and Copy to.
+#[tauri::command]
+pub fn execute_action(
+    kind: ActionKind,
+    result: ActionTarget,
+    with: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    if !actions::for_result(&result.result_type)
+        .iter()
+        .any(|a| a.kind == kind)
+    {
+        return Err(CommandError::LaunchError(format!(
+            "{:?} does not apply to this result",
+            kind
+        )));
+    }
+    let io_error = |e: std::io::Error| CommandError::LaunchError(e.to_string());
+
+    if kind.takes_path() {
+        let path = validate_user_path(&result.exec)?;
+        return match kind {
+            ActionKind::OpenWith => {
+                let with = with.ok_or_else(|| {
+                    CommandError::LaunchError("No application chosen".to_string())
+                })?;
+                open_with_app(&state, &path, &with)
+            }
+            ActionKind::Reveal => actions::reveal(&path).map_err(io_error),
+            ActionKind::CopyPath => copy_to_clipboard(result.exec, app),
+            ActionKind::CopyName => copy_to_clipboard(result.name, app),
+            ActionKind::OpenInTerminal => {
+                let dir = if path.is_dir() {
+                    path.as_path()
+                } else {
+                    path.parent().unwrap_or(&path)
+                };
+                let terminal = commands_config(&state).terminal;
+                actions::open_terminal(dir, terminal.as_deref()).map_err(io_error)
+            }
+            // These run through `trash_path`, `rename_path` and so on
+            ActionKind::Rename
+            | ActionKind::Duplicate
+            | ActionKind::MoveTo
+            | ActionKind::CopyTo
+            | ActionKind::Trash => Err(CommandError::LaunchError(format!(
+                "{:?} has its own command",
+                kind
+            ))),
+            _ => open_path(result.exec),
+        };
+    }
+
+    if result.result_type == ResultType::Shell {
+        // Copy Output, for the command that last ran
+        let output = {
+            let capture = state.shell.lock().unwrap_or_else(|e| e.into_inner());
+            let command = result.exec.strip_prefix("shell:").unwrap_or(&result.exec);
+            (capture.run > 0 && capture.command == command).then(|| capture.output.clone())
+        };
+        let output =
+            output.ok_or_else(|| CommandError::LaunchError("Run the command first".to_string()))?;
+        return copy_to_clipboard(output, app);
+    }
+
+    if result.result_type == ResultType::Command {
+        let terminal = Some(kind == ActionKind::RunInTerminal);
+        return match run_command(result.exec, terminal, false, state)? {
+            RunOutcome::Started => Ok(()),
+            RunOutcome::Confirm { path } => Err(CommandError::LaunchError(format!(
+                "{} is not in an allowed location",
+                path
+            ))),
+        };
+    }
+
+    ensure_indexed_app(&state, &result.exec)?;
+    match kind {
+        ActionKind::LaunchNewInstance => {
+            launch_with(&result.exec, &[], true, &allowed_dirs(&state))
+        }
+        ActionKind::ShowDesktopFile => {
+            #[cfg(target_os = "linux")]
+            let file = crate::indexer::desktop_file(&result.exec).ok_or_else(|| {
+                CommandError::LaunchError("Desktop file not found".to_string())
+            })?;
+            #[cfg(not(target_os = "linux"))]
+            let file = PathBuf::from(&result.exec);
+            actions::reveal(&file).map_err(io_error)
+        }
+        ActionKind::QuitInstances => {
+            // The bundle on macOS, the app the Exec line starts elsewhere
+            #[cfg(target_os = "macos")]
+            let program = PathBuf::from(&result.exec);
+            #[cfg(not(target_os = "macos"))]
+            let program = actions::quit_target(&strip_field_codes(&result.exec)).map_err(|e| {
+                CommandError::LaunchError(format!("Can't quit {}: {}", result.name, e))
+            })?;
+            match actions::quit_instances(&program).map_err(io_error)? {
+                0 => Err(CommandError::LaunchError(format!("{} is not running", result.name))),
+                _ => Ok(()),
+            }
+        }
+        _ => launch_app(result.exec, state),
+    }
+}
+
+/// Move `path` to the trash.
+#[tauri::command]
+pub fn trash_path(path: String, state: State<'_, AppState>) -> Result<(), FileOpError> {
+    let entry = fileops::entry_under_home(Path::new(&path))?;
+    fileops::trash(&entry)?;
+    relocate_in_indexes(&state, Path::new(&path), None);
+    Ok(())
+}
+
+/// Rename `path` in place to `name`. Returns the new path.
+#[tauri::command]
+pub fn rename_path(
+    path: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<String, FileOpError> {
+    let entry = fileops::entry_under_home(Path::new(&path))?;
+    let renamed = fileops::rename(&entry, &name)?;
+    relocate_in_indexes(&state, Path::new(&path), Some(&renamed));
+    Ok(path_string(renamed))
+}
+
+/// Copy `path` next to itself. Returns the copy's path.
+#[tauri::command]
+pub fn duplicate_path(path: String) -> Result<String, FileOpError> {
+    let path = fileops::entry_under_home(Path::new(&path))?;
+    fileops::duplicate(&path).map(path_string)
+}
+
+/// Move `path` into `folder`. Returns the new path.
+#[tauri::command]
+pub fn move_path(
+    path: String,
+    folder: String,
+    state: State<'_, AppState>,
+) -> Result<String, FileOpError> {
+    let entry = fileops::entry_under_home(Path::new(&path))?;
+    let folder = fileops::under_home(Path::new(&folder))?;
+    let moved = fileops::move_to(&entry, &folder)?;
+    relocate_in_indexes(&state, Path::new(&path), Some(&moved));
+    Ok(path_string(moved))
+}
+
+/// Copy `path` into `folder`. Returns the copy's path.
+#[tauri::command]
+pub fn copy_path(path: String, folder: String) -> Result<String, FileOpError> {
+    let path = fileops::entry_under_home(Path::new(&path))?;
+    let folder = fileops::under_home(Path::new(&folder))?;
+    fileops::copy_to(&path, &folder).map(path_string)
+}
+
+/// Keep the folder and image indexes, if built, pointing at where `from`
+/// went; `None` when it was trashed.
+fn relocate_in_indexes(state: &AppState, from: &Path, to: Option<&Path>) {
+    for index in [&state.folder_index, &state.image_index] {
+        let mut index = index.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(entries) = index.as_mut() {
+            crate::indexer::relocate(entries, from, to);
+        }
+    }
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Apps that can open `path`, best first. A `filter` narrows them and also
+/// brings up other apps. Each result's `exec` is what `execute_action`
+/// takes as `with`.
+#[tauri::command]
+pub fn get_open_with_apps(
+    path: String,
+    filter: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<AppResult>, CommandError> {
+    let path = validate_user_path(&path)?;
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut choices = state.open_with.lock().unwrap_or_else(|e| e.into_inner());
+        // An empty filter is the picker opening, so look again
+        if filter.is_empty() || choices.path != path {
+            *choices = open_with_choices(&state, &path);
+        }
+        if filter.is_empty() {
+            return Ok(choices.entries[..choices.handlers]
+                .iter()
+                .map(AppResult::from)
+                .collect());
+        }
+        let mut matcher = state.matcher.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(rank(&mut matcher, &filter, &choices.entries, MAX_RESULTS))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        Ok(search_apps(filter, state)
+            .into_iter()
+            .filter(|a| a.result_type == ResultType::App)
+            .collect())
+    }
+}
+
+/// The handlers of `path`'s type and the other listed apps.
+#[cfg(target_os = "linux")]
+fn open_with_choices(state: &AppState, path: &Path) -> OpenWithChoices {
+    let db = state.mime_db.get_or_init(mime::MimeDb::load);
+    let mime = db.guess(path);
+    let apps = crate::indexer::desktop_apps();
+    let installed: Vec<(&str, &[String])> = apps
+        .iter()
+        .map(|app| (app.id.as_str(), app.mime_types.as_slice()))
+        .collect();
+    let handlers = open_with::rank_handlers(
+        &db.with_parents(&mime),
+        open_with::remembered(&mime).as_deref(),
+        &open_with::load_mimeapps(),
+        &installed,
+    );
+
+    // Desktop file IDs stand in for execs, which needn't be unique
+    let as_choice = |app: &crate::indexer::DesktopApp, note: Option<&str>| AppEntry {
+        exec: app.id.clone(),
+        description: note.map(str::to_string),
+        ..app.entry.clone()
+    };
+    let mut entries: Vec<AppEntry> = handlers
+        .iter()
+        .filter_map(|h| {
+            let app = apps.iter().find(|app| app.id == h.id)?;
+            Some(as_choice(app, h.note))
+        })
+        .collect();
+    let count = entries.len();
+    entries.extend(
+        apps.iter()
+            .filter(|app| !app.no_display && !handlers.iter().any(|h| h.id == app.id))
+            .map(|app| as_choice(app, None)),
+    );
+    OpenWithChoices {
+        path: path.to_path_buf(),
+        mime,
+        apps,
+        entries,
+        handlers: count,
+    }
+}
+
+/// Open `path` with the app `get_open_with_apps` offered as `with`, and
+/// offer it first for files of the same type next time.
+#[cfg(target_os = "linux")]
+fn open_with_app(state: &AppState, path: &Path, with: &str) -> Result<(), CommandError> {
+    let mut choices = state.open_with.lock().unwrap_or_else(|e| e.into_inner());
+    if choices.path != path {
+        *choices = open_with_choices(state, path);
+    }
+    let app = choices
+        .apps
+        .iter()
+        .find(|app| app.id == with)
+        .ok_or_else(|| CommandError::LaunchError("Not an installed application".to_string()))?;
+    let args = open_with::exec_args(
+        &app.entry.exec,
+        path,
+        &app.entry.name,
+        app.entry.icon.as_deref(),
+        &app.path,
+    );
+    let program = validate_exec(&args, &allowed_dirs(state))?;
+
+    Command::new(&program)
+        .args(&args[1..])
+        .spawn()
+        .map_err(|e| CommandError::LaunchError(e.to_string()))?;
+
+    if let Err(e) = open_with::remember(&choices.mime, with) {
+        eprintln!("Warning: failed to save Open With choice: {}", e);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn open_with_app(state: &AppState, path: &Path, with: &str) -> Result<(), CommandError> {
+    ensure_indexed_app(state, with)?;
+    launch_with(with, &[path], false, &allowed_dirs(state))
+}
+
 #[tauri::command]
 pub fn browse_directory(path: String, filter: String) -> Result<Vec<AppResult>, CommandError> {
     let dir = std::path::Path::new(&path);
@@ -533,6 +1499,12 @@ pub fn browse_directory(path: String, filter: String) -> Result<Vec<AppResult>,
                 crate::indexer::ResultType::Image => 2,
                 crate::indexer::ResultType::System => 3,
                 crate::indexer::ResultType::File => 4,
+                crate::indexer::ResultType::Calculator => 5,
+                crate::indexer::ResultType::Command => 6,
+                crate::indexer::ResultType::Shell => 7,
+                crate::indexer::ResultType::Script => 8,
+                crate::indexer::ResultType::WebSearch => 9,
+                crate::indexer::ResultType::Quicklink => 10,
             };
             type_ord(&a.result_type)
                 .cmp(&type_ord(&b.result_type))
@@ -551,9 +1523,10 @@ pub fn browse_directory(path: String, filter: String) -> Result<Vec<AppResult>,
 }
 
 /// Strip freedesktop field codes from exec strings (%u, %U, %f, %F, etc.)
+/// along with the `@@`/`@@u` … `@@` markers Flatpak puts around them.
 fn strip_field_codes(exec: &str) -> String {
     exec.split_whitespace()
-        .filter(|part| !part.starts_with('%'))
+        .filter(|part| !part.starts_with('%') && !matches!(*part, "@@" | "@@u"))
         .collect::<Vec<_>>()
         .join(" ")
 }
@@ -562,6 +1535,20 @@ fn strip_field_codes(exec: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_exec_path_resolves_bare_names() {
+        let root = [PathBuf::from("/")];
+        let sh = validate_exec_path("sh", &root).unwrap();
+        assert!(sh.is_absolute());
+        assert!(validate_exec_path("sh", &[PathBuf::from("/nonexistent")]).is_err());
+        assert!(validate_exec_path("cheru-no-such-program", &root).is_err());
+        assert!(validate_exec_path("./sh", &root).is_err());
+
+        // What env runs is checked too
+        assert!(validate_exec(&["env", "A=1", "-i", "sh"], &root).is_ok());
+        assert!(validate_exec(&["env", "A=1", "cheru-no-such-program"], &root).is_err());
+    }
+
     #[test]
     fn test_strip_field_codes() {
         assert_eq!(strip_field_codes("firefox %u"), "firefox");
@@ -571,5 +1558,9 @@ mod tests {
             "gimp --new-instance"
         );
         assert_eq!(strip_field_codes("nautilus"), "nautilus");
+        assert_eq!(
+            strip_field_codes("/usr/bin/flatpak run --file-forwarding org.gimp.GIMP @@ %F @@"),
+            "/usr/bin/flatpak run --file-forwarding org.gimp.GIMP"
+        );
     }
 }
@@ -1,17 +1,163 @@
-use serde::{Deserialize, Serialize};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use toml::{Table, Value};
 
-#[derive(Debug, Deserialize)]
+use crate::allowlist;
+use crate::paths;
+use crate::themes::{self, ThemeConfig};
+use crate::web;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
-    #[serde(default = "default_hotkey")]
+    /// Shortcut that toggles the launcher; same as `[hotkeys] toggle`
     pub hotkey: String,
-    #[serde(default = "default_theme")]
+    /// Every global shortcut, including the toggle one
+    pub hotkeys: Vec<Hotkey>,
     pub theme: String,
-    #[serde(default = "default_autostart")]
+    /// Theme used while the system prefers a light appearance
+    pub light_theme: Option<String>,
     pub autostart: bool,
-    #[serde(default)]
     pub colors: HashMap<String, String>,
+    pub calculator: CalculatorConfig,
+    pub launch: LaunchConfig,
+    pub commands: CommandsConfig,
+    /// `[[quicklink]]` entries, in file order
+    pub quicklinks: Vec<Quicklink>,
+    /// `[bangs]` entries; `keyword` is the trigger without its `!`
+    pub bangs: Vec<Quicklink>,
+    pub web: WebConfig,
+}
+
+/// A global shortcut that shows the launcher, optionally pre-filled.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hotkey {
+    /// Name of the binding in `[hotkeys]`
+    pub mode: String,
+    pub key: String,
+    /// Initial query; `None` toggles the window instead
+    pub query: Option<String>,
+}
+
+/// Built-in `[hotkeys]` modes and the query each one opens with.
+const HOTKEY_MODES: &[(&str, Option<&str>)] = &[
+    ("toggle", None),
+    ("search", Some("")),
+    ("files", Some(">")),
+    ("calc", Some("calc history ")),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalculatorConfig {
+    /// Maximum number of fraction digits shown in results
+    pub precision: usize,
+    /// Language tag used for decimal and thousands separators, e.g. "en", "de-DE"
+    pub locale: String,
+    /// Group integer digits with the locale's thousands separator
+    pub thousands_separator: bool,
+}
+
+impl Default for CalculatorConfig {
+    fn default() -> Self {
+        Self {
+            precision: 10,
+            locale: "en".to_string(),
+            thousands_separator: false,
+        }
+    }
+}
+
+/// Where launched programs may live.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LaunchConfig {
+    /// Folders allowed besides the defaults, as written (`~/` allowed)
+    pub allowed_paths: Vec<String>,
+    /// Include the platform's default folders
+    pub default_paths: bool,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_paths: Vec::new(),
+            default_paths: true,
+        }
+    }
+}
+
+impl LaunchConfig {
+    /// The folders launched programs may live in.
+    pub fn allowed_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = if self.default_paths {
+            allowlist::default_dirs()
+        } else {
+            Vec::new()
+        };
+        dirs.extend(
+            self.allowed_paths
+                .iter()
+                .filter_map(|p| allowlist::expand_home(p)),
+        );
+        dirs
+    }
+}
+
+/// Running executables from `$PATH` as commands.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandsConfig {
+    /// List `$PATH` executables and accept `!command args`
+    pub enabled: bool,
+    /// Enter runs commands in a terminal instead of in the background
+    pub in_terminal: bool,
+    /// Terminal to use before `$TERMINAL` and the known ones
+    pub terminal: Option<String>,
+    /// Accept `$ command` and run it with the user's shell
+    pub shell: bool,
+    /// Seconds a `$` command may run before it is killed
+    pub shell_timeout: u64,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            in_terminal: true,
+            terminal: None,
+            shell: false,
+            shell_timeout: 30,
+        }
+    }
+}
+
+/// A URL opened by name, or searched with `keyword terms` when it has a
+/// `{query}` placeholder.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Quicklink {
+    pub name: String,
+    pub keyword: String,
+    pub url: String,
+}
+
+/// Web searches and the URLs the launcher opens.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebConfig {
+    /// Engine offered when little else matches: a built-in engine or a
+    /// quicklink keyword
+    pub fallback: String,
+    /// URL schemes `open_url` and quicklinks may use
+    pub allowed_schemes: Vec<String>,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            fallback: "google".to_string(),
+            allowed_schemes: vec!["https".to_string()],
+        }
+    }
 }
 
 fn default_hotkey() -> String {
@@ -30,43 +176,264 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             hotkey: default_hotkey(),
+            hotkeys: vec![Hotkey {
+                mode: "toggle".to_string(),
+                key: default_hotkey(),
+                query: None,
+            }],
             theme: default_theme(),
+            light_theme: None,
             colors: HashMap::new(),
             autostart: default_autostart(),
+            calculator: CalculatorConfig::default(),
+            launch: LaunchConfig::default(),
+            commands: CommandsConfig::default(),
+            quicklinks: Vec::new(),
+            bangs: Vec::new(),
+            web: WebConfig::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct ThemeConfig {
-    pub theme: String,
-    pub colors: HashMap<String, String>,
+impl Config {
+    /// Resolve the selected theme(s) with `[colors]` layered on top.
+    pub fn theme_config(&self) -> ThemeConfig {
+        let resolve = |name: &str| {
+            let (mut theme, _) = themes::resolve(name);
+            theme.colors.extend(self.colors.clone());
+            theme
+        };
+        let mut theme = resolve(&self.theme);
+        theme.light = self
+            .light_theme
+            .as_deref()
+            .map(|name| Box::new(resolve(name)));
+        theme
+    }
 }
 
-pub fn load() -> Config {
-    let path = config_path();
+/// Load the config, keeping every valid setting. Problems are printed and
+/// returned so the launcher can show them.
+pub fn load() -> (Config, Vec<Diagnostic>) {
+    let path = paths::config_file();
     match std::fs::read_to_string(&path) {
-        Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
-            eprintln!("Warning: invalid config at {}: {}", path.display(), e);
-            Config::default()
-        }),
+        Ok(contents) => {
+            let (cfg, diagnostics) = parse_checked(&contents);
+            for d in &diagnostics {
+                eprintln!("{}:{}", path.display(), d);
+            }
+            (cfg, diagnostics)
+        }
         Err(_) => {
             // Create default config file for discoverability
             if let Some(parent) = path.parent() {
                 let _ = std::fs::create_dir_all(parent);
             }
-            let default_content = r##"# Cheru launcher configuration
+            let _ = std::fs::write(&path, DEFAULT_CONFIG);
+            (Config::default(), Vec::new())
+        }
+    }
+}
+
+/// Validate the config file for `cheru --check-config`. Prints one line per
+/// problem and returns the process exit code.
+pub fn check() -> i32 {
+    let path = paths::config_file();
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return 1;
+        }
+    };
+    let (_, diagnostics) = parse_checked(&contents);
+    for d in &diagnostics {
+        println!("{}:{}", path.display(), d);
+    }
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        1
+    } else {
+        if diagnostics.is_empty() {
+            println!("{}: OK", path.display());
+        }
+        0
+    }
+}
+
+/// Set one key (dotted for tables, e.g. `calculator.precision`) in
+/// config.toml, preserving comments and ordering. The watcher then applies it.
+pub fn set_value(key: &str, value: &serde_json::Value) -> Result<(), String> {
+    let path = paths::config_file();
+    // Write through symlinks so dotfile-managed configs stay linked
+    let path = path.canonicalize().unwrap_or(path);
+    let contents = std::fs::read_to_string(&path).unwrap_or_else(|_| DEFAULT_CONFIG.to_string());
+    let updated = edit(&contents, key, value)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // Replace atomically so the watcher never sees a half-written file
+    let tmp = path.with_extension("toml.tmp");
+    std::fs::write(&tmp, updated).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+/// Apply a single-key edit to config text, rejecting keys config.toml
+/// doesn't have and values that would not validate.
+fn edit(contents: &str, key: &str, value: &serde_json::Value) -> Result<String, String> {
+    let mut doc = contents.parse::<toml_edit::DocumentMut>().map_err(|e| {
+        format!(
+            "config.toml has a syntax error, fix it first: {}",
+            e.message()
+        )
+    })?;
+
+    let new_value: toml_edit::Value = match value {
+        serde_json::Value::String(s) => s.as_str().into(),
+        serde_json::Value::Bool(b) => (*b).into(),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => i.into(),
+            (None, Some(f)) => f.into(),
+            _ => return Err(format!("unsupported number `{}`", n)),
+        },
+        other => return Err(format!("unsupported value `{}`", other)),
+    };
+
+    let (sections, leaf) = match key.rsplit_once('.') {
+        Some((sections, leaf)) => (sections.split('.').collect(), leaf),
+        None => (Vec::new(), key),
+    };
+    let mut table = doc.as_table_mut();
+    for section in sections {
+        table = table
+            .entry(section)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .ok_or_else(|| format!("`{}` is not a table", section))?;
+    }
+    if table.get(leaf).is_some_and(|item| !item.is_value()) {
+        return Err(format!("`{}` is not a single value", key));
+    }
+    match table.get_mut(leaf).and_then(|item| item.as_value_mut()) {
+        Some(existing) => {
+            // Keep the spacing and any trailing comment on the line
+            let decor = existing.decor().clone();
+            *existing = new_value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(leaf, toml_edit::value(new_value));
+        }
+    }
+
+    let updated = doc.to_string();
+    // Any new problem is the edit's, including an unknown-key warning
+    let problems = |text: &str| {
+        parse(text)
+            .1
+            .into_iter()
+            .map(|d| d.message)
+            .collect::<Vec<_>>()
+    };
+    let before = problems(contents);
+    if let Some(message) = problems(&updated).into_iter().find(|m| !before.contains(m)) {
+        return Err(message);
+    }
+    Ok(updated)
+}
+
+/// Values offered by the launcher's settings entries, for keys that have a
+/// fixed set of choices.
+pub fn choices(key: &str) -> Vec<serde_json::Value> {
+    match key {
+        "theme" | "light_theme" => themes::available()
+            .into_iter()
+            .map(serde_json::Value::String)
+            .collect(),
+        "autostart"
+        | "calculator.thousands_separator"
+        | "launch.default_paths"
+        | "commands.enabled"
+        | "commands.in_terminal"
+        | "commands.shell" => {
+            vec![serde_json::json!(true), serde_json::json!(false)]
+        }
+        "web.fallback" => web::ENGINES
+            .iter()
+            .map(|(keyword, _, _)| serde_json::json!(keyword))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+const DEFAULT_CONFIG: &str = r##"# Cheru launcher configuration
 
 # Hotkey to toggle the launcher window
 # Examples: "Alt+Space", "Cmd+D", "Ctrl+Space", "Cmd+Shift+K"
 hotkey = "Alt+Space"
 
-# Theme: "gruvbox" (default), "dark", "dracula", "one-dark"
+# Theme: "gruvbox" (default), "dark", "dracula", "one-dark", or the name of
+# a file in the themes/ directory next to this one (e.g. themes/nord.toml)
 theme = "gruvbox"
 
+# Switch to this theme while the system uses a light appearance (optional)
+# light_theme = "my-light-theme"
+
 # Auto-start Cheru on login (true/false)
 autostart = true
 
+# Extra shortcuts that open the launcher in a specific mode (optional)
+# Built-in modes: toggle, search, files (content search), calc (history)
+# [hotkeys]
+# files = "Alt+F"
+# calc = "Alt+C"
+# downloads = { key = "Alt+D", query = "downloads/" }
+
+# Calculator result formatting (optional)
+# [calculator]
+# precision = 10              # maximum fraction digits
+# locale = "en"               # separators: "en" 1,234.5 / "de" 1.234,5 / "fr" 1 234,5 / "de-CH" 1'234.5
+# thousands_separator = false
+
+# Where launched programs may live (optional). The defaults cover system
+# folders, /opt, Flatpak, Snap, Nix, ~/.local/bin and ~/Applications.
+# [launch]
+# allowed_paths = ["~/bin", "/mnt/tools"]  # allowed besides the defaults
+# default_paths = true                     # false to allow only allowed_paths
+
+# Run executables from $PATH (optional, off by default). Type a name, or
+# `!command args` to run exactly that. Programs outside the [launch]
+# folders ask for confirmation first.
+# [commands]
+# enabled = true
+# in_terminal = true   # Enter runs in a terminal; false runs in the background
+# terminal = "kitty"   # used before $TERMINAL (Linux; macOS runs Terminal)
+# shell = true         # `$ command` runs with $SHELL -c and shows the output
+# shell_timeout = 30   # seconds before a `$` command is killed
+
+# Quicklinks (optional): `gh tauri` searches GitHub for "tauri". A quicklink
+# without {query} in its URL opens as it is.
+# [[quicklink]]
+# name = "GitHub"
+# keyword = "gh"
+# url = "https://github.com/search?q={query}"
+#
+# [[quicklink]]
+# name = "docs.rs"
+# keyword = "crate"
+# url = "https://docs.rs/releases/search?query={query}"
+
+# Web search (optional)
+# [web]
+# fallback = "google"          # google, duckduckgo, bing, brave, startpage, kagi or a quicklink keyword
+# allowed_schemes = ["https"]  # URL schemes quicklinks and web results may open
+
+# DuckDuckGo-style bangs (optional): `rust book !w` searches Wikipedia. Many
+# are built in (!g, !w, !gh, !rs, !yt, ...); these add to or replace them.
+# [bangs]
+# gh = "https://github.com/search?type=code&q={query}"
+# jira = { name = "Jira", url = "https://jira.example.com/browse/{query}" }
+
 # Custom color overrides (optional)
 # These override any theme's colors. Use CSS color values.
 # [colors]
@@ -80,16 +447,1487 @@ autostart = true
 # accent = "#d79921"
 # border = "rgba(235, 219, 178, 0.08)"
 "##;
-            let _ = std::fs::write(&path, default_content);
-            Config::default()
+
+const COLOR_KEYS: &[&str] = &[
+    "bg_primary",
+    "bg_secondary",
+    "bg_hover",
+    "bg_selected",
+    "bg_actionbar",
+    "text_primary",
+    "text_secondary",
+    "text_placeholder",
+    "accent",
+    "border",
+    "border_light",
+    "shadow",
+];
+
+/// Stop retrying after this many syntax errors; the file is likely garbled.
+const MAX_SYNTAX_ERRORS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Severity {
+    /// The setting was rejected and its default is used instead
+    Error,
+    /// The setting was ignored, e.g. an unknown key
+    Warning,
+}
+
+/// A problem found in config.toml, with a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, severity, self.message
+        )
+    }
+}
+
+/// Collects diagnostics, locating keys in the source text since `toml::Table`
+/// does not keep spans.
+struct Checker<'a> {
+    contents: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    /// Which `[[table]]` of an array of tables is being checked
+    entry: usize,
+}
+
+impl<'a> Checker<'a> {
+    fn report(&mut self, severity: Severity, table: Option<&str>, key: &str, message: String) {
+        let (line, column) = locate_entry_key(self.contents, table, self.entry, key);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line,
+            column,
+            message,
+        });
+    }
+
+    fn invalid(&mut self, table: Option<&str>, key: &str, message: String) {
+        self.report(Severity::Error, table, key, message);
+    }
+
+    fn unknown(&mut self, table: Option<&str>, key: &str, known: &[&str]) {
+        let full = match table {
+            Some(t) => format!("{}.{}", t, key),
+            None => key.to_string(),
+        };
+        let message = match suggest(key, known) {
+            Some(s) => format!("unknown key `{}`, did you mean `{}`?", full, s),
+            None => format!("unknown key `{}`", full),
+        };
+        self.report(Severity::Warning, table, key, message);
+    }
+
+    fn string(&mut self, table: Option<&str>, key: &str, value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            other => {
+                self.invalid(
+                    table,
+                    key,
+                    format!("`{}` must be a string, found {}", key, other.type_str()),
+                );
+                None
+            }
+        }
+    }
+
+    fn boolean(&mut self, table: Option<&str>, key: &str, value: &Value) -> Option<bool> {
+        match value {
+            Value::Boolean(b) => Some(*b),
+            other => {
+                self.invalid(
+                    table,
+                    key,
+                    format!(
+                        "`{}` must be true or false, found {}",
+                        key,
+                        other.type_str()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn table<'v>(&mut self, key: &str, value: &'v Value) -> Option<&'v Table> {
+        match value {
+            Value::Table(t) => Some(t),
+            other => {
+                self.invalid(
+                    None,
+                    key,
+                    format!("`{}` must be a table, found {}", key, other.type_str()),
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Parse config.toml, keeping valid settings and rejecting only broken ones.
+pub fn parse(contents: &str) -> (Config, Vec<Diagnostic>) {
+    let mut checker = Checker {
+        contents,
+        diagnostics: Vec::new(),
+        entry: 0,
+    };
+    let table = parse_table(contents, &mut checker.diagnostics);
+    // The toggle binding is added after `hotkey` and `[hotkeys]` are read
+    let mut cfg = Config {
+        hotkeys: Vec::new(),
+        ..Config::default()
+    };
+
+    for (key, value) in &table {
+        match key.as_str() {
+            "hotkey" => {
+                if let Some(hotkey) = checker.string(None, key, value) {
+                    match validate_hotkey(&hotkey) {
+                        Ok(()) => cfg.hotkey = hotkey,
+                        Err(e) => checker.invalid(
+                            None,
+                            key,
+                            format!("invalid hotkey `{}`: {}", hotkey, e),
+                        ),
+                    }
+                }
+            }
+            "theme" | "light_theme" => {
+                if let Some(theme) = checker.string(None, key, value) {
+                    if !themes::exists(&theme) {
+                        checker.invalid(
+                            None,
+                            key,
+                            format!(
+                                "unknown theme `{}`, expected one of: {}",
+                                theme,
+                                themes::available().join(", ")
+                            ),
+                        );
+                    } else if key == "theme" {
+                        cfg.theme = theme;
+                    } else {
+                        cfg.light_theme = Some(theme);
+                    }
+                }
+            }
+            "autostart" => {
+                if let Some(autostart) = checker.boolean(None, key, value) {
+                    cfg.autostart = autostart;
+                }
+            }
+            "colors" => {
+                if let Some(colors) = checker.table(key, value) {
+                    cfg.colors = check_colors(&mut checker, colors);
+                }
+            }
+            "calculator" => {
+                if let Some(calculator) = checker.table(key, value) {
+                    cfg.calculator = check_calculator(&mut checker, calculator);
+                }
+            }
+            "hotkeys" => {
+                if let Some(hotkeys) = checker.table(key, value) {
+                    cfg.hotkeys = check_hotkeys(&mut checker, hotkeys);
+                }
+            }
+            "launch" => {
+                if let Some(launch) = checker.table(key, value) {
+                    cfg.launch = check_launch(&mut checker, launch);
+                }
+            }
+            "commands" => {
+                if let Some(commands) = checker.table(key, value) {
+                    cfg.commands = check_commands(&mut checker, commands);
+                }
+            }
+            "web" => {
+                if let Some(web) = checker.table(key, value) {
+                    cfg.web = check_web(&mut checker, web);
+                }
+            }
+            // Checked below, once `[web] allowed_schemes` is known
+            "quicklink" | "bangs" => {}
+            _ => checker.unknown(
+                None,
+                key,
+                &[
+                    "hotkey",
+                    "theme",
+                    "light_theme",
+                    "hotkeys",
+                    "autostart",
+                    "colors",
+                    "calculator",
+                    "launch",
+                    "commands",
+                    "quicklink",
+                    "bangs",
+                    "web",
+                ],
+            ),
+        }
+    }
+
+    if let Some(quicklinks) = table.get("quicklink") {
+        cfg.quicklinks = check_quicklinks(&mut checker, quicklinks, &cfg.web.allowed_schemes);
+    }
+    if let Some(bangs) = table.get("bangs").and_then(|v| checker.table("bangs", v)) {
+        cfg.bangs = check_bangs(&mut checker, bangs, &cfg.web.allowed_schemes);
+    }
+    let fallback = &cfg.web.fallback;
+    let known = web::ENGINES
+        .iter()
+        .any(|(keyword, _, _)| keyword == fallback)
+        || cfg
+            .quicklinks
+            .iter()
+            .any(|q| &q.keyword == fallback && web::takes_query(&q.url));
+    if !known {
+        let engines: Vec<&str> = web::ENGINES
+            .iter()
+            .map(|(keyword, _, _)| *keyword)
+            .collect();
+        checker.invalid(
+            Some("web"),
+            "fallback",
+            format!(
+                "unknown engine `{}`, expected one of: {} or a quicklink keyword",
+                fallback,
+                engines.join(", ")
+            ),
+        );
+        cfg.web.fallback = WebConfig::default().fallback;
+    }
+
+    // `hotkey` is shorthand for `[hotkeys] toggle`, which wins if both are set
+    match cfg.hotkeys.iter().find(|h| h.mode == "toggle") {
+        Some(toggle) => cfg.hotkey = toggle.key.clone(),
+        None => {
+            if let Some(i) = cfg
+                .hotkeys
+                .iter()
+                .position(|h| same_hotkey(&h.key, &cfg.hotkey))
+            {
+                let taken = cfg.hotkeys.remove(i);
+                checker.invalid(
+                    Some("hotkeys"),
+                    &taken.mode,
+                    format!("`{}` is already bound to `toggle`", taken.key),
+                );
+            }
+            cfg.hotkeys.insert(
+                0,
+                Hotkey {
+                    mode: "toggle".to_string(),
+                    key: cfg.hotkey.clone(),
+                    query: None,
+                },
+            );
+        }
+    }
+
+    checker.diagnostics.sort_by_key(|d| (d.line, d.column));
+    (cfg, checker.diagnostics)
+}
+
+fn check_colors(checker: &mut Checker, colors: &Table) -> HashMap<String, String> {
+    let section = Some("colors");
+    let mut valid = HashMap::new();
+    for (key, value) in colors {
+        if !COLOR_KEYS.contains(&key.as_str()) {
+            checker.unknown(section, key, COLOR_KEYS);
+            continue;
+        }
+        let Some(color) = checker.string(section, key, value) else {
+            continue;
+        };
+        if is_valid_theme_color(key, &color) {
+            valid.insert(key.clone(), color);
+        } else {
+            checker.invalid(
+                section,
+                key,
+                format!("invalid CSS value for `{}`: `{}`", key, color),
+            );
+        }
+    }
+    valid
+}
+
+/// Each entry is `mode = "Accel"` for a built-in mode, or
+/// `name = { key = "Accel", query = "downloads/" }` for a custom query.
+fn check_hotkeys(checker: &mut Checker, hotkeys: &Table) -> Vec<Hotkey> {
+    let section = Some("hotkeys");
+    let modes: Vec<&str> = HOTKEY_MODES.iter().map(|(m, _)| *m).collect();
+    let mut valid: Vec<Hotkey> = Vec::new();
+
+    for (mode, value) in hotkeys {
+        let (key, query) = match value {
+            Value::String(key) => match HOTKEY_MODES.iter().find(|(m, _)| m == mode) {
+                Some((_, query)) => (key.clone(), query.map(str::to_string)),
+                None => {
+                    checker.invalid(
+                        section,
+                        mode,
+                        format!(
+                            "unknown hotkey mode `{}`, expected one of: {} (or use `{} = {{ key = \"...\", query = \"...\" }}`)",
+                            mode,
+                            modes.join(", "),
+                            mode
+                        ),
+                    );
+                    continue;
+                }
+            },
+            Value::Table(t) => match (t.get("key"), t.get("query")) {
+                (Some(Value::String(key)), Some(Value::String(query))) => {
+                    (key.clone(), Some(query.clone()))
+                }
+                _ => {
+                    checker.invalid(
+                        section,
+                        mode,
+                        format!("`{}` needs string `key` and `query` fields", mode),
+                    );
+                    continue;
+                }
+            },
+            other => {
+                checker.invalid(
+                    section,
+                    mode,
+                    format!(
+                        "`{}` must be a string or a table, found {}",
+                        mode,
+                        other.type_str()
+                    ),
+                );
+                continue;
+            }
+        };
+
+        if let Err(e) = validate_hotkey(&key) {
+            checker.invalid(section, mode, format!("invalid hotkey `{}`: {}", key, e));
+            continue;
+        }
+        if let Some(other) = valid.iter().find(|h| same_hotkey(&h.key, &key)) {
+            checker.invalid(
+                section,
+                mode,
+                format!("`{}` is already bound to `{}`", key, other.mode),
+            );
+            continue;
+        }
+        valid.push(Hotkey {
+            mode: mode.clone(),
+            key,
+            query,
+        });
+    }
+    valid
+}
+
+fn check_calculator(checker: &mut Checker, calculator: &Table) -> CalculatorConfig {
+    let section = Some("calculator");
+    let mut cfg = CalculatorConfig::default();
+    for (key, value) in calculator {
+        match key.as_str() {
+            "precision" => match value {
+                Value::Integer(n) if (0..=50).contains(n) => cfg.precision = *n as usize,
+                Value::Integer(n) => checker.invalid(
+                    section,
+                    key,
+                    format!("`precision` must be between 0 and 50, found {}", n),
+                ),
+                other => checker.invalid(
+                    section,
+                    key,
+                    format!("`precision` must be an integer, found {}", other.type_str()),
+                ),
+            },
+            "locale" => {
+                if let Some(locale) = checker.string(section, key, value) {
+                    cfg.locale = locale;
+                }
+            }
+            "thousands_separator" => {
+                if let Some(b) = checker.boolean(section, key, value) {
+                    cfg.thousands_separator = b;
+                }
+            }
+            _ => checker.unknown(
+                section,
+                key,
+                &["precision", "locale", "thousands_separator"],
+            ),
+        }
+    }
+    cfg
+}
+
+fn check_launch(checker: &mut Checker, launch: &Table) -> LaunchConfig {
+    let section = Some("launch");
+    let mut cfg = LaunchConfig::default();
+    for (key, value) in launch {
+        match key.as_str() {
+            "allowed_paths" => {
+                let Value::Array(paths) = value else {
+                    checker.invalid(
+                        section,
+                        key,
+                        format!(
+                            "`allowed_paths` must be an array of paths, found {}",
+                            value.type_str()
+                        ),
+                    );
+                    continue;
+                };
+                for path in paths {
+                    match path {
+                        Value::String(p) if allowlist::expand_home(p).is_some() => {
+                            cfg.allowed_paths.push(p.clone())
+                        }
+                        Value::String(p) => checker.invalid(
+                            section,
+                            key,
+                            format!("`{}` must be absolute or start with `~/`", p),
+                        ),
+                        other => checker.invalid(
+                            section,
+                            key,
+                            format!(
+                                "`allowed_paths` entries must be strings, found {}",
+                                other.type_str()
+                            ),
+                        ),
+                    }
+                }
+            }
+            "default_paths" => {
+                if let Some(b) = checker.boolean(section, key, value) {
+                    cfg.default_paths = b;
+                }
+            }
+            _ => checker.unknown(section, key, &["allowed_paths", "default_paths"]),
+        }
+    }
+    cfg
+}
+
+fn check_commands(checker: &mut Checker, commands: &Table) -> CommandsConfig {
+    let section = Some("commands");
+    let mut cfg = CommandsConfig::default();
+    for (key, value) in commands {
+        match key.as_str() {
+            "enabled" => {
+                if let Some(b) = checker.boolean(section, key, value) {
+                    cfg.enabled = b;
+                }
+            }
+            "in_terminal" => {
+                if let Some(b) = checker.boolean(section, key, value) {
+                    cfg.in_terminal = b;
+                }
+            }
+            "terminal" => match checker.string(section, key, value) {
+                Some(t) if t.trim().is_empty() => {
+                    checker.invalid(section, key, "`terminal` must not be empty".to_string())
+                }
+                Some(t) => cfg.terminal = Some(t),
+                None => {}
+            },
+            "shell" => {
+                if let Some(b) = checker.boolean(section, key, value) {
+                    cfg.shell = b;
+                }
+            }
+            "shell_timeout" => match value {
+                Value::Integer(n) if (1..=3600).contains(n) => cfg.shell_timeout = *n as u64,
+                Value::Integer(n) => checker.invalid(
+                    section,
+                    key,
+                    format!("`shell_timeout` must be between 1 and 3600, found {}", n),
+                ),
+                other => checker.invalid(
+                    section,
+                    key,
+                    format!(
+                        "`shell_timeout` must be an integer, found {}",
+                        other.type_str()
+                    ),
+                ),
+            },
+            _ => checker.unknown(
+                section,
+                key,
+                &[
+                    "enabled",
+                    "in_terminal",
+                    "terminal",
+                    "shell",
+                    "shell_timeout",
+                ],
+            ),
+        }
+    }
+    cfg
+}
+
+fn check_web(checker: &mut Checker, web: &Table) -> WebConfig {
+    let section = Some("web");
+    let mut cfg = WebConfig::default();
+    for (key, value) in web {
+        match key.as_str() {
+            "fallback" => {
+                if let Some(fallback) = checker.string(section, key, value) {
+                    cfg.fallback = fallback;
+                }
+            }
+            "allowed_schemes" => {
+                let schemes = match value {
+                    Value::Array(schemes) => schemes
+                        .iter()
+                        .map(|s| {
+                            s.as_str()
+                                .filter(|s| web::scheme(&format!("{}:", s)).is_some())
+                        })
+                        .collect::<Option<Vec<_>>>(),
+                    _ => None,
+                };
+                match schemes {
+                    Some(schemes) => {
+                        cfg.allowed_schemes =
+                            schemes.into_iter().map(|s| s.to_ascii_lowercase()).collect()
+                    }
+                    None => checker.invalid(
+                        section,
+                        key,
+                        "`allowed_schemes` must be an array of URL schemes, e.g. [\"https\", \"http\"]"
+                            .to_string(),
+                    ),
+                }
+            }
+            _ => checker.unknown(section, key, &["fallback", "allowed_schemes"]),
+        }
+    }
+    cfg
+}
+
+/// Each `[[quicklink]]` needs a name, a keyword and a URL whose scheme is
+/// allowed. Broken entries are dropped.
+fn check_quicklinks(
+    checker: &mut Checker,
+    quicklinks: &Value,
+    schemes: &[String],
+) -> Vec<Quicklink> {
+    let section = Some("quicklink");
+    let Value::Array(entries) = quicklinks else {
+        checker.invalid(
+            None,
+            "quicklink",
+            "`quicklink` must be an array of tables, written [[quicklink]]".to_string(),
+        );
+        return Vec::new();
+    };
+    let mut valid: Vec<Quicklink> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        checker.entry = i;
+        let Value::Table(entry) = entry else {
+            checker.invalid(
+                None,
+                "quicklink",
+                "`quicklink` must be an array of tables, written [[quicklink]]".to_string(),
+            );
+            continue;
+        };
+        let mut fields = [None, None, None];
+        for (key, value) in entry {
+            let field = match key.as_str() {
+                "name" => &mut fields[0],
+                "keyword" => &mut fields[1],
+                "url" => &mut fields[2],
+                _ => {
+                    checker.unknown(section, key, &["name", "keyword", "url"]);
+                    continue;
+                }
+            };
+            match checker.string(section, key, value) {
+                Some(v) if v.trim().is_empty() => {
+                    checker.invalid(section, key, format!("`{}` must not be empty", key))
+                }
+                Some(v) => *field = Some(v),
+                None => {}
+            }
+        }
+        let [Some(name), Some(keyword), Some(url)] = fields else {
+            // Fields with a bad value have been reported already
+            for key in ["name", "keyword", "url"] {
+                if !entry.contains_key(key) {
+                    checker.invalid(section, key, format!("quicklink is missing `{}`", key));
+                }
+            }
+            continue;
+        };
+
+        if !is_keyword(&keyword) {
+            checker.invalid(
+                section,
+                "keyword",
+                format!(
+                    "keyword `{}` may only use letters, digits, `-`, `_` and `.`",
+                    keyword
+                ),
+            );
+        } else if valid
+            .iter()
+            .any(|q| q.keyword.eq_ignore_ascii_case(&keyword))
+        {
+            checker.invalid(
+                section,
+                "keyword",
+                format!("keyword `{}` is already used by another quicklink", keyword),
+            );
+        } else if !web::is_allowed(&url, schemes) {
+            checker.invalid(
+                section,
+                "url",
+                format!(
+                    "`{}` is not an allowed URL; [web] allowed_schemes is {}",
+                    url,
+                    schemes.join(", ")
+                ),
+            );
+        } else {
+            valid.push(Quicklink { name, keyword, url });
+        }
+    }
+    checker.entry = 0;
+    valid
+}
+
+/// `trigger = "URL"` or `trigger = { name = "Site", url = "URL" }`, the
+/// trigger written without its `!`. A plain URL is named after the bundled
+/// bang it replaces, or else its host.
+fn check_bangs(checker: &mut Checker, bangs: &Table, schemes: &[String]) -> Vec<Quicklink> {
+    let section = Some("bangs");
+    let mut valid: Vec<Quicklink> = Vec::new();
+    for (trigger, value) in bangs {
+        let (name, url) = match value {
+            Value::String(url) => (None, url.clone()),
+            Value::Table(t) => match (t.get("name"), t.get("url")) {
+                (Some(Value::String(name)), Some(Value::String(url))) if t.len() == 2 => {
+                    (Some(name.clone()), url.clone())
+                }
+                _ => {
+                    checker.invalid(
+                        section,
+                        trigger,
+                        format!("`{}` needs string `name` and `url` fields", trigger),
+                    );
+                    continue;
+                }
+            },
+            other => {
+                checker.invalid(
+                    section,
+                    trigger,
+                    format!(
+                        "`{}` must be a URL or a table, found {}",
+                        trigger,
+                        other.type_str()
+                    ),
+                );
+                continue;
+            }
+        };
+
+        if !is_keyword(trigger) {
+            checker.invalid(
+                section,
+                trigger,
+                format!(
+                    "bang `{}` may only use letters, digits, `-`, `_` and `.`",
+                    trigger
+                ),
+            );
+        } else if valid
+            .iter()
+            .any(|b| b.keyword.eq_ignore_ascii_case(trigger))
+        {
+            checker.invalid(
+                section,
+                trigger,
+                format!("bang `{}` is already defined", trigger),
+            );
+        } else if !web::is_allowed(&url, schemes) {
+            checker.invalid(
+                section,
+                trigger,
+                format!(
+                    "`{}` is not an allowed URL; [web] allowed_schemes is {}",
+                    url,
+                    schemes.join(", ")
+                ),
+            );
+        } else {
+            let name = name
+                .or_else(|| web::lookup_bang(trigger, &[]).map(|(site, _)| site))
+                .or_else(|| web::host(&url).map(str::to_string))
+                .unwrap_or_else(|| trigger.clone());
+            valid.push(Quicklink {
+                name,
+                keyword: trigger.to_lowercase(),
+                url,
+            });
         }
     }
+    valid
+}
+
+/// Quicklink keywords and bang triggers: letters, digits, `-`, `_` and `.`.
+fn is_keyword(keyword: &str) -> bool {
+    !keyword.is_empty()
+        && keyword
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.".contains(c))
 }
 
-fn config_path() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_default()
-        .join(".config")
-        .join("cheru")
-        .join("config.toml")
+/// Report problems in the user theme files the config selects, at the line
+/// that selects them.
+/// `parse`, plus problems with the selected themes' files.
+fn parse_checked(contents: &str) -> (Config, Vec<Diagnostic>) {
+    let (cfg, mut diagnostics) = parse(contents);
+    diagnostics.extend(check_themes(contents, &cfg));
+    (cfg, diagnostics)
+}
+
+fn check_themes(contents: &str, cfg: &Config) -> Vec<Diagnostic> {
+    let selected = [
+        ("theme", Some(&cfg.theme)),
+        ("light_theme", cfg.light_theme.as_ref()),
+    ];
+    let mut diagnostics = Vec::new();
+    for (key, name) in selected {
+        let Some(name) = name else { continue };
+        for problem in themes::resolve(name).1 {
+            let (line, column) = locate_key(contents, None, key);
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                line,
+                column,
+                message: problem,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Parse the document into a table. On a syntax error the offending line is
+/// commented out and parsing retried, so one typo doesn't discard the file.
+fn parse_table(contents: &str, diagnostics: &mut Vec<Diagnostic>) -> Table {
+    let mut text = contents.to_string();
+    for _ in 0..MAX_SYNTAX_ERRORS {
+        match text.parse::<Table>() {
+            Ok(table) => return table,
+            Err(e) => {
+                let offset = e.span().map(|s| s.start).unwrap_or(0);
+                let (line, column) = line_col(&text, offset);
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    line,
+                    column,
+                    message: e.message().trim().to_string(),
+                });
+                text = text
+                    .lines()
+                    .enumerate()
+                    .map(|(i, l)| {
+                        if i + 1 == line {
+                            format!("# {}", l)
+                        } else {
+                            l.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+            }
+        }
+    }
+    Table::new()
+}
+
+/// 1-based line and column of a byte offset.
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+/// Find where `key` is defined, within `[table]` if given. Falls back to the
+/// table header, then to the top of the file.
+fn locate_key(contents: &str, table: Option<&str>, key: &str) -> (usize, usize) {
+    locate_entry_key(contents, table, 0, key)
+}
+
+/// As `locate_key`, within the `entry`th `[[table]]` of an array of tables.
+fn locate_entry_key(
+    contents: &str,
+    table: Option<&str>,
+    entry: usize,
+    key: &str,
+) -> (usize, usize) {
+    let mut current: Option<&str> = None;
+    let mut seen = 0;
+    let mut header = None;
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if let Some(rest) = trimmed.strip_prefix('[') {
+            let name = rest
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or("")
+                .trim();
+            current = Some(name);
+            if Some(name) == table {
+                seen += 1;
+                if seen == entry + 1 {
+                    header = Some((i + 1, indent + 1));
+                }
+            }
+            continue;
+        }
+        if current == table && (table.is_none() || seen == entry + 1) {
+            if let Some(pos) = find_key(trimmed, key) {
+                return (i + 1, indent + pos + 1);
+            }
+        }
+    }
+    header.unwrap_or((1, 1))
+}
+
+/// Byte position of `key` used as a key (followed by `=`) in `text`.
+fn find_key(text: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", key);
+    text.match_indices(key)
+        .chain(text.match_indices(quoted.as_str()))
+        .map(|(pos, m)| (pos, pos + m.len()))
+        .find(|&(start, end)| {
+            let inside_word = matches!(
+                text[..start].chars().next_back(),
+                Some(c) if c.is_alphanumeric() || c == '_' || c == '-'
+            );
+            !inside_word && text[end..].trim_start().starts_with('=')
+        })
+        .map(|(start, _)| start)
+}
+
+/// Closest known key by edit distance, for typos like `hotky`.
+fn suggest<'k>(key: &str, known: &[&'k str]) -> Option<&'k str> {
+    known
+        .iter()
+        .map(|k| (*k, edit_distance(key, k)))
+        .filter(|(_, d)| *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(k, _)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+fn same_hotkey(a: &str, b: &str) -> bool {
+    use tauri_plugin_global_shortcut::Shortcut;
+    match (a.parse::<Shortcut>(), b.parse::<Shortcut>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn validate_hotkey(hotkey: &str) -> Result<(), String> {
+    hotkey
+        .parse::<tauri_plugin_global_shortcut::Shortcut>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+const NAMED_COLORS: &[&str] = &[
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+    "transparent",
+    "currentcolor",
+];
+
+/// Whether `value` is acceptable for the color key `key`, in `[colors]` or
+/// a theme file.
+pub(crate) fn is_valid_theme_color(key: &str, value: &str) -> bool {
+    if !COLOR_KEYS.contains(&key) {
+        return false;
+    }
+    if key == "shadow" {
+        is_valid_shadow(value)
+    } else {
+        is_valid_color(value)
+    }
+}
+
+/// Accepts hex, `rgb()`/`rgba()`/`hsl()`/`hsla()` and named CSS colors.
+fn is_valid_color(value: &str) -> bool {
+    let v = value.trim().to_lowercase();
+    if let Some(hex) = v.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    for func in ["rgba", "rgb", "hsla", "hsl"] {
+        if let Some(args) = v
+            .strip_prefix(func)
+            .and_then(|r| r.trim_start().strip_prefix('('))
+        {
+            let Some(args) = args.strip_suffix(')') else {
+                return false;
+            };
+            let parts: Vec<&str> = args
+                .split([',', '/', ' '])
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .collect();
+            return (3..=4).contains(&parts.len())
+                && parts.iter().all(|p| {
+                    let n = p.trim_end_matches('%').trim_end_matches("deg");
+                    !n.is_empty() && n.parse::<f64>().is_ok()
+                });
+        }
+    }
+    NAMED_COLORS.contains(&v.as_str())
+}
+
+/// Box shadows are free-form, but must not be able to break out of the
+/// CSS custom property they are assigned to.
+fn is_valid_shadow(value: &str) -> bool {
+    !value.trim().is_empty() && !value.contains([';', '{', '}'])
+}
+
+/// How long to wait for a burst of file events (write, rename, chmod) from a
+/// single save to settle before reloading.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Call `on_change` whenever the config file or a user theme is saved. The
+/// directories are watched rather than the files themselves, since many
+/// editors save by renaming a temporary file over the original.
+pub fn watch<F>(on_change: F) -> notify::Result<()>
+where
+    F: Fn() + Send + 'static,
+{
+    let path = paths::config_file();
+    let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    // A symlinked config, as `set_value` writes it, changes where it points
+    let target = path.canonicalize().unwrap_or_else(|_| path.clone());
+    let (tx, rx) = mpsc::channel();
+    let themes_dir = themes::themes_dir();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    if let Some(target_dir) = target.parent().filter(|d| *d != dir) {
+        let _ = watcher.watch(target_dir, RecursiveMode::NonRecursive);
+    }
+    // Themes are optional; the directory may not exist yet
+    let _ = watcher.watch(&themes_dir, RecursiveMode::NonRecursive);
+
+    std::thread::spawn(move || {
+        // The watcher stops when dropped, so it lives as long as this thread
+        let mut watcher = watcher;
+        while let Ok(event) = rx.recv() {
+            let Ok(event) = event else { continue };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            if matches!(event.kind, EventKind::Create(_)) && event.paths.contains(&themes_dir) {
+                let _ = watcher.watch(&themes_dir, RecursiveMode::NonRecursive);
+            }
+            let relevant = event.paths.iter().any(|p| {
+                p == &path
+                    || p == &target
+                    || (p.parent() == Some(themes_dir.as_path())
+                        && p.extension().is_some_and(|e| e == "toml"))
+            });
+            if !relevant {
+                continue;
+            }
+            while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+            if path.exists() {
+                on_change();
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_config_has_no_diagnostics() {
+        let (cfg, diagnostics) = parse(DEFAULT_CONFIG);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(cfg.hotkey, "Alt+Space");
+        assert_eq!(cfg.theme, "gruvbox");
+    }
+
+    #[test]
+    fn test_invalid_field_keeps_the_rest() {
+        let src = "hotkey = \"Ctrl+Space\"\ntheme = \"dracula\"\nautostart = \"yes\"\n\n[colors]\naccent = \"#ff00zz\"\ntext_primary = \"#fff\"\n";
+        let (cfg, diagnostics) = parse(src);
+        assert_eq!(cfg.hotkey, "Ctrl+Space");
+        assert_eq!(cfg.theme, "dracula");
+        assert!(cfg.autostart);
+        assert_eq!(
+            cfg.colors.get("text_primary").map(String::as_str),
+            Some("#fff")
+        );
+        assert!(!cfg.colors.contains_key("accent"));
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 1));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (6, 1));
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    }
+
+    #[test]
+    fn test_bad_hotkey_and_theme() {
+        let (cfg, diagnostics) = parse("hotkey = \"Alt+Spcae\"\ntheme = \"solarized\"\n");
+        assert_eq!(cfg.hotkey, "Alt+Space");
+        assert_eq!(cfg.theme, "gruvbox");
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.contains("Alt+Spcae"));
+        assert!(diagnostics[1].message.contains("gruvbox, dark"));
+    }
+
+    #[test]
+    fn test_check_reports_missing_theme() {
+        let (cfg, diagnostics) = parse_checked("theme = \"missing\"\n");
+        assert_eq!(cfg.theme, "gruvbox");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("missing"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+    }
+
+    #[test]
+    fn test_unknown_key_suggests_closest() {
+        let (_, diagnostics) = parse("hotky = \"Alt+Space\"\n[calculator]\n  precison = 4\n");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert!(diagnostics[0].message.contains("did you mean `hotkey`"));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (3, 3));
+        assert!(diagnostics[1].message.contains("did you mean `precision`"));
+    }
+
+    #[test]
+    fn test_syntax_error_keeps_other_lines() {
+        let (cfg, diagnostics) =
+            parse("theme = \"dark\"\nautostart = tru\n\n[calculator]\nprecision = 3\n");
+        assert_eq!(cfg.theme, "dark");
+        assert_eq!(cfg.calculator.precision, 3);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_calculator_ranges() {
+        let (cfg, diagnostics) = parse("[calculator]\nprecision = 99\nlocale = \"de\"\n");
+        assert_eq!(cfg.calculator.precision, 10);
+        assert_eq!(cfg.calculator.locale, "de");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("between 0 and 50"));
+    }
+
+    #[test]
+    fn test_launch_allowed_paths() {
+        let (cfg, diagnostics) = parse(
+            "[launch]\nallowed_paths = [\"~/bin\", \"tools\", \"/mnt/apps\"]\ndefault_paths = false\n",
+        );
+        assert_eq!(cfg.launch.allowed_paths, vec!["~/bin", "/mnt/apps"]);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("`tools` must be absolute"));
+
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            cfg.launch.allowed_dirs(),
+            vec![home.join("bin"), PathBuf::from("/mnt/apps")]
+        );
+        let defaults = Config::default().launch.allowed_dirs();
+        assert_eq!(defaults, allowlist::default_dirs());
+    }
+
+    #[test]
+    fn test_commands_section() {
+        let (cfg, diagnostics) =
+            parse("[commands]\nenabled = true\nin_terminal = false\nterminal = \"foot\"\n");
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            cfg.commands,
+            CommandsConfig {
+                enabled: true,
+                in_terminal: false,
+                terminal: Some("foot".to_string()),
+                ..CommandsConfig::default()
+            }
+        );
+        assert!(!Config::default().commands.enabled);
+
+        let (cfg, diagnostics) = parse("[commands]\nshell = true\nshell_timeout = 5\n");
+        assert!(diagnostics.is_empty());
+        assert!(cfg.commands.shell);
+        assert_eq!(cfg.commands.shell_timeout, 5);
+
+        let (cfg, diagnostics) =
+            parse("[commands]\nenabled = \"yes\"\nterminal = \" \"\nshell_timeout = 0\n");
+        assert_eq!(cfg.commands, CommandsConfig::default());
+        assert_eq!(diagnostics.len(), 3);
+    }
+
+    #[test]
+    fn test_quicklinks_and_web() {
+        let (cfg, diagnostics) = parse(
+            r#"[web]
+fallback = "crate"
+allowed_schemes = ["https", "HTTP"]
+
+[[quicklink]]
+name = "docs.rs"
+keyword = "crate"
+url = "https://docs.rs/releases/search?query={query}"
+
+[[quicklink]]
+name = "Jira"
+keyword = "jira"
+url = "http://jira.internal/secure/QuickSearch.jspa?searchString={query}"
+"#,
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(cfg.web.allowed_schemes, ["https", "http"]);
+        assert_eq!(cfg.web.fallback, "crate");
+        let keywords: Vec<&str> = cfg.quicklinks.iter().map(|q| q.keyword.as_str()).collect();
+        assert_eq!(keywords, ["crate", "jira"]);
+
+        let contents = r#"[[quicklink]]
+name = "GitHub"
+keyword = "gh"
+url = "https://github.com/search?q={query}"
+
+[[quicklink]]
+name = "Intranet"
+keyword = "GH"
+url = "https://intranet.example.com"
+
+[[quicklink]]
+name = "Jira"
+keyword = "jira"
+url = "http://jira.internal/?q={query}"
+
+[[quicklink]]
+keyword = "x"
+url = "https://example.com"
+
+[web]
+fallback = "altavista"
+"#;
+        let (cfg, diagnostics) = parse(contents);
+        assert_eq!(cfg.quicklinks.len(), 1);
+        assert_eq!(cfg.web, WebConfig::default());
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        // Duplicate keyword, http not allowed, missing name, unknown fallback
+        assert_eq!(lines, [8, 14, 16, 21]);
+    }
+
+    #[test]
+    fn test_bangs() {
+        let (cfg, diagnostics) = parse(
+            r#"[bangs]
+gh = "https://github.com/search?type=code&q={query}"
+jira = { name = "Jira", url = "https://jira.example.com/browse/{query}" }
+wiki = "https://wiki.example.com/?s={query}"
+"#,
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let names: Vec<(&str, &str)> = cfg
+            .bangs
+            .iter()
+            .map(|b| (b.keyword.as_str(), b.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("gh", "GitHub"),
+                ("jira", "Jira"),
+                ("wiki", "wiki.example.com")
+            ]
+        );
+
+        let (cfg, diagnostics) = parse(
+            r#"[bangs]
+plain = "http://example.com/?q={query}"
+"a b" = "https://x.com"
+yt = { url = "https://x.com" }
+w = 3
+"#,
+        );
+        assert!(cfg.bangs.is_empty());
+        assert_eq!(diagnostics.len(), 4);
+    }
+
+    #[test]
+    fn test_css_colors() {
+        for ok in [
+            "#abc",
+            "#aabbccdd",
+            "rgba(40, 40, 40, 0.92)",
+            "rgb(1 2 3 / 50%)",
+            "hsl(120deg, 50%, 50%)",
+            "RebeccaPurple",
+            "transparent",
+        ] {
+            assert!(is_valid_color(ok), "{}", ok);
+        }
+        for bad in [
+            "#abcde",
+            "rgba(1, 2)",
+            "rgb(1, 2, 3",
+            "blurple",
+            "red; color: blue",
+        ] {
+            assert!(!is_valid_color(bad), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_edit_preserves_comments() {
+        let src = "# my config\nhotkey = \"Alt+Space\"\ntheme = \"gruvbox\"  # favourite\n";
+        let out = edit(src, "theme", &serde_json::json!("dracula")).unwrap();
+        assert_eq!(
+            out,
+            "# my config\nhotkey = \"Alt+Space\"\ntheme = \"dracula\"  # favourite\n"
+        );
+
+        let out = edit(&out, "calculator.precision", &serde_json::json!(4)).unwrap();
+        assert!(out.ends_with("[calculator]\nprecision = 4\n"), "{}", out);
+        assert_eq!(parse(&out).0.calculator.precision, 4);
+    }
+
+    #[test]
+    fn test_edit_rejects_invalid_values() {
+        let src = "theme = \"gruvbox\"\n";
+        assert!(edit(src, "theme", &serde_json::json!("nope")).is_err());
+        assert!(edit(src, "hotkey", &serde_json::json!("Alt+")).is_err());
+        assert!(edit(src, "autostart", &serde_json::json!("yes")).is_err());
+        assert!(edit("theme = \"gruvbox\n", "theme", &serde_json::json!("dark")).is_err());
+
+        // Only keys config.toml has, and only single values
+        assert!(edit(src, "them", &serde_json::json!("dark")).is_err());
+        assert!(edit(src, "calculator.precison", &serde_json::json!(4)).is_err());
+        assert!(edit(src, "nope.theme", &serde_json::json!("dark")).is_err());
+        let links =
+            "[[quicklink]]\nname = \"GH\"\nkeyword = \"gh\"\nurl = \"https://github.com\"\n";
+        assert!(edit(links, "quicklink", &serde_json::json!("x")).is_err());
+    }
+
+    #[test]
+    fn test_hotkey_modes() {
+        let src = "hotkey = \"Alt+Space\"\n\n[hotkeys]\nfiles = \"Alt+F\"\ndownloads = { key = \"Alt+D\", query = \"downloads/\" }\nclipboard = \"Alt+V\"\ncalc = \"Alt+Space\"\n";
+        let (cfg, diagnostics) = parse(src);
+        let bound: Vec<(&str, Option<&str>)> = cfg
+            .hotkeys
+            .iter()
+            .map(|h| (h.mode.as_str(), h.query.as_deref()))
+            .collect();
+        assert_eq!(
+            bound,
+            vec![
+                ("toggle", None),
+                ("downloads", Some("downloads/")),
+                ("files", Some(">")),
+            ]
+        );
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics[0]
+            .message
+            .contains("unknown hotkey mode `clipboard`"));
+        assert!(diagnostics[1].message.contains("already bound to `toggle`"));
+    }
 }
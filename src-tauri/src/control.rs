@@ -0,0 +1,164 @@
+//! Control socket for scripts, tests and window-manager bindings.
+//!
+//! Clients send one JSON object per line, e.g.
+//! `{"method": "Search", "query": "fire"}`, and get one line back:
+//! `{"ok": true, "result": ...}` or `{"ok": false, "error": "..."}`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+
+use crate::paths;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "method")]
+pub enum Request {
+    Show {
+        #[serde(default)]
+        query: Option<String>,
+    },
+    Hide,
+    Toggle,
+    /// Returns the same results as typing `query` into the launcher
+    Search {
+        query: String,
+    },
+    /// `id` is the `exec` of a search result
+    Launch {
+        id: String,
+    },
+    Reindex,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Answer one request line.
+fn respond<F>(line: &str, handler: &F) -> String
+where
+    F: Fn(Request) -> Result<Value, String>,
+{
+    let outcome = serde_json::from_str::<Request>(line)
+        .map_err(|e| format!("invalid request: {}", e))
+        .and_then(handler);
+    let response = match outcome {
+        Ok(result) => Response {
+            ok: true,
+            result: (!result.is_null()).then_some(result),
+            error: None,
+        },
+        Err(error) => Response {
+            ok: false,
+            result: None,
+            error: Some(error),
+        },
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// Listen on the control socket in the background.
+pub fn serve<F>(handler: F) -> std::io::Result<()>
+where
+    F: Fn(Request) -> Result<Value, String> + Send + Sync + 'static,
+{
+    let path = paths::socket_file();
+    if let Some(dir) = path.parent() {
+        // Only the user may reach the socket, even before it is chmodded.
+        // The folder is Cheru's own; the one holding it is left as it is.
+        if let Some(base) = dir.parent() {
+            std::fs::create_dir_all(base)?;
+        }
+        if let Err(e) = std::fs::DirBuilder::new().mode(0o700).create(dir) {
+            if e.kind() != std::io::ErrorKind::AlreadyExists {
+                return Err(e);
+            }
+        }
+        if std::fs::metadata(dir)?.permissions().mode() & 0o077 != 0 {
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    // Left behind by a previous run; single-instance means no one else owns it
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let handler = Arc::new(handler);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handler = Arc::clone(&handler);
+            std::thread::spawn(move || serve_client(stream, handler.as_ref()));
+        }
+    });
+    Ok(())
+}
+
+fn serve_client<F>(stream: UnixStream, handler: &F)
+where
+    F: Fn(Request) -> Result<Value, String>,
+{
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let mut writer = stream;
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = respond(&line, handler);
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(request: Request) -> Result<Value, String> {
+        match request {
+            Request::Search { query } => Ok(serde_json::json!([query])),
+            Request::Launch { id } => Err(format!("cannot launch {}", id)),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    #[test]
+    fn test_parses_requests() {
+        let parse = |s: &str| serde_json::from_str::<Request>(s).unwrap();
+        assert_eq!(parse(r#"{"method":"Show"}"#), Request::Show { query: None });
+        assert_eq!(
+            parse(r#"{"method":"Show","query":"downloads/"}"#),
+            Request::Show {
+                query: Some("downloads/".to_string())
+            }
+        );
+        assert_eq!(parse(r#"{"method":"Reindex"}"#), Request::Reindex);
+    }
+
+    #[test]
+    fn test_responses() {
+        assert_eq!(
+            respond(r#"{"method":"Search","query":"fire"}"#, &echo),
+            r#"{"ok":true,"result":["fire"]}"#
+        );
+        assert_eq!(respond(r#"{"method":"Hide"}"#, &echo), r#"{"ok":true}"#);
+        assert_eq!(
+            respond(r#"{"method":"Launch","id":"x"}"#, &echo),
+            r#"{"ok":false,"error":"cannot launch x"}"#
+        );
+        assert!(respond(r#"{"method":"Explode"}"#, &echo)
+            .starts_with(r#"{"ok":false,"error":"invalid request"#));
+    }
+}
@@ -0,0 +1,392 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Common abbreviations mapped to a representative zone, so daylight saving
+/// is applied for the date being converted.
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("utc", "UTC"),
+    ("gmt", "Etc/GMT"),
+    ("pst", "America/Los_Angeles"),
+    ("pdt", "America/Los_Angeles"),
+    ("pt", "America/Los_Angeles"),
+    ("mst", "America/Denver"),
+    ("mdt", "America/Denver"),
+    ("cst", "America/Chicago"),
+    ("cdt", "America/Chicago"),
+    ("est", "America/New_York"),
+    ("edt", "America/New_York"),
+    ("et", "America/New_York"),
+    ("bst", "Europe/London"),
+    ("cet", "Europe/Paris"),
+    ("cest", "Europe/Paris"),
+    ("eet", "Europe/Athens"),
+    ("eest", "Europe/Athens"),
+    ("msk", "Europe/Moscow"),
+    ("ist", "Asia/Kolkata"),
+    ("sgt", "Asia/Singapore"),
+    ("hkt", "Asia/Hong_Kong"),
+    ("jst", "Asia/Tokyo"),
+    ("kst", "Asia/Seoul"),
+    ("aest", "Australia/Sydney"),
+    ("aedt", "Australia/Sydney"),
+    ("nzst", "Pacific/Auckland"),
+    ("nzdt", "Pacific/Auckland"),
+];
+
+const DATE_FORMAT: &str = "%a, %-d %b %Y";
+const DATETIME_FORMAT: &str = "%a, %-d %b %Y %H:%M";
+
+/// A resolved time zone: a named IANA zone, or the system's local offset.
+enum Zone {
+    Named(Tz),
+    Local(FixedOffset),
+}
+
+impl Zone {
+    fn localize(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Zone::Named(tz) => tz.from_local_datetime(&naive).earliest().map(|d| d.fixed_offset()),
+            Zone::Local(offset) => offset.from_local_datetime(&naive).single(),
+        }
+    }
+
+    fn today(&self, now: DateTime<FixedOffset>) -> NaiveDate {
+        match self {
+            Zone::Named(tz) => now.with_timezone(tz).date_naive(),
+            Zone::Local(offset) => now.with_timezone(offset).date_naive(),
+        }
+    }
+
+    /// Format `instant` in this zone, labelled with the zone abbreviation.
+    fn format(&self, instant: DateTime<FixedOffset>, fmt: &str) -> String {
+        match self {
+            Zone::Named(tz) => instant.with_timezone(tz).format(&format!("{} %Z", fmt)).to_string(),
+            Zone::Local(offset) => instant.with_timezone(offset).format(fmt).to_string(),
+        }
+    }
+}
+
+/// Evaluate a date/time query against the system clock.
+pub fn evaluate(input: &str) -> Option<String> {
+    evaluate_at(input, Local::now().fixed_offset())
+}
+
+/// Evaluate a date/time query relative to `now`, whose offset is treated as
+/// the local time zone.
+pub fn evaluate_at(input: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let query = input.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+
+    parse_timestamp(&query, now)
+        .or_else(|| parse_days_until(&query, now))
+        .or_else(|| parse_date_offset(&query, now))
+        .or_else(|| parse_now_in(&query, now))
+        .or_else(|| parse_conversion(&query, now))
+}
+
+/// `timestamp now`, `unix now`, `unix 1700000000`, `timestamp 1700000000000`
+fn parse_timestamp(query: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let rest = query
+        .strip_prefix("unix ")
+        .or_else(|| query.strip_prefix("timestamp "))
+        .or_else(|| query.strip_prefix("epoch "))?
+        .trim();
+
+    if rest == "now" {
+        return Some(now.timestamp().to_string());
+    }
+
+    let value: i64 = rest.parse().ok()?;
+    // Values this large are almost certainly milliseconds
+    let instant = if value.unsigned_abs() >= 100_000_000_000 {
+        DateTime::<Utc>::from_timestamp_millis(value)?
+    } else {
+        DateTime::<Utc>::from_timestamp(value, 0)?
+    };
+    Some(instant.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
+/// `days until 2026-12-25`, `days since 2020-01-01`
+fn parse_days_until(query: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let (date, sign) = if let Some(rest) = query.strip_prefix("days until ") {
+        (rest, 1)
+    } else if let Some(rest) = query.strip_prefix("days since ") {
+        (rest, -1)
+    } else {
+        return None;
+    };
+
+    let today = Zone::Local(*now.offset()).today(now);
+    let target = parse_date(date.trim(), today)?;
+    let days = (target - today).num_days() * sign;
+    Some(if days.abs() == 1 {
+        format!("{} day", days)
+    } else {
+        format!("{} days", days)
+    })
+}
+
+/// `today + 45 days`, `tomorrow - 2 weeks`, `now + 90 minutes`
+fn parse_date_offset(query: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    // The last sign, so dashes in an ISO date stay part of the base
+    let at = query.rfind(['+', '-'])?;
+    let (base, rest) = query.split_at(at);
+    let (op, rest) = rest.split_at(1);
+    let (base, rest) = (base.trim(), rest.trim());
+
+    let (amount, unit) = rest.split_once(' ')?;
+    let amount: u32 = amount.parse().ok()?;
+    let unit = unit.trim().trim_end_matches('s');
+
+    let local = Zone::Local(*now.offset());
+    match base {
+        "now" => {
+            let delta = match unit {
+                "second" | "sec" => Duration::seconds(amount.into()),
+                "minute" | "min" => Duration::minutes(amount.into()),
+                "hour" | "hr" => Duration::hours(amount.into()),
+                "day" => Duration::days(amount.into()),
+                "week" => Duration::weeks(amount.into()),
+                _ => return None,
+            };
+            let result = if op == "+" {
+                now.checked_add_signed(delta)
+            } else {
+                now.checked_sub_signed(delta)
+            }?;
+            Some(local.format(result, DATETIME_FORMAT))
+        }
+        _ => {
+            let base = parse_date(base, local.today(now))?;
+            let result = match unit {
+                "day" | "week" => {
+                    let days = if unit == "week" {
+                        amount.checked_mul(7)?
+                    } else {
+                        amount
+                    };
+                    let delta = Duration::days(days.into());
+                    if op == "+" {
+                        base.checked_add_signed(delta)
+                    } else {
+                        base.checked_sub_signed(delta)
+                    }
+                }
+                "month" | "year" => {
+                    let months = if unit == "year" {
+                        amount.checked_mul(12)?
+                    } else {
+                        amount
+                    };
+                    let months = Months::new(months);
+                    if op == "+" {
+                        base.checked_add_months(months)
+                    } else {
+                        base.checked_sub_months(months)
+                    }
+                }
+                _ => None,
+            }?;
+            Some(result.format(DATE_FORMAT).to_string())
+        }
+    }
+}
+
+/// `now in tokyo`, `time in new york`, `tokyo time`
+fn parse_now_in(query: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let place = query
+        .strip_prefix("now in ")
+        .or_else(|| query.strip_prefix("time in "))
+        .or_else(|| query.strip_suffix(" time"))?;
+    let zone = resolve_zone(place.trim(), now)?;
+    Some(zone.format(now, DATETIME_FORMAT))
+}
+
+/// `3pm pst to cet`, `15:30 in tokyo`, `9am tokyo to local`
+fn parse_conversion(query: &str, now: DateTime<FixedOffset>) -> Option<String> {
+    let (from, to) = query
+        .split_once(" to ")
+        .or_else(|| query.split_once(" in "))?;
+
+    let (time, from_zone) = match from.trim().split_once(' ') {
+        Some((time, zone)) => (parse_time(time)?, resolve_zone(zone.trim(), now)?),
+        None => (parse_time(from.trim())?, Zone::Local(*now.offset())),
+    };
+    let to_zone = resolve_zone(to.trim(), now)?;
+
+    let date = from_zone.today(now);
+    let instant = from_zone.localize(date.and_time(time))?;
+    Some(to_zone.format(instant, DATETIME_FORMAT))
+}
+
+fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match input {
+        "today" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "yesterday" => today.pred_opt(),
+        _ => NaiveDate::parse_from_str(input, "%Y-%m-%d").ok(),
+    }
+}
+
+/// `3pm`, `3:30pm`, `15:00`, `noon`, `midnight`
+fn parse_time(input: &str) -> Option<NaiveTime> {
+    match input {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, meridiem) = if let Some(t) = input.strip_suffix("am") {
+        (t, Some(false))
+    } else if let Some(t) = input.strip_suffix("pm") {
+        (t, Some(true))
+    } else {
+        (input, None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None if meridiem.is_some() => (clock.parse::<u32>().ok()?, 0),
+        // A bare number is not a time ("15 in tokyo" is ambiguous)
+        None => return None,
+    };
+
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Resolve an abbreviation (`pst`), IANA name (`europe/paris`) or city (`new york`).
+fn resolve_zone(input: &str, now: DateTime<FixedOffset>) -> Option<Zone> {
+    if input == "local" || input == "here" {
+        return Some(Zone::Local(*now.offset()));
+    }
+
+    if let Some((_, name)) = ABBREVIATIONS.iter().find(|(abbr, _)| *abbr == input) {
+        return name.parse::<Tz>().ok().map(Zone::Named);
+    }
+
+    let city = input.replace(' ', "_");
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .find(|tz| tz.name().eq_ignore_ascii_case(&city))
+        .or_else(|| {
+            chrono_tz::TZ_VARIANTS.iter().find(|tz| {
+                tz.name()
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|last| last.eq_ignore_ascii_case(&city))
+            })
+        })
+        .copied()
+        .map(Zone::Named)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saturday 2026-10-17 12:00 UTC, with a UTC local zone
+    fn clock() -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap().fixed_offset()
+    }
+
+    #[test]
+    fn now_in_city() {
+        assert_eq!(
+            evaluate_at("now in Tokyo", clock()),
+            Some("Sat, 17 Oct 2026 21:00 JST".into())
+        );
+        assert_eq!(
+            evaluate_at("time in new york", clock()),
+            Some("Sat, 17 Oct 2026 08:00 EDT".into())
+        );
+    }
+
+    #[test]
+    fn convert_between_zones() {
+        assert_eq!(
+            evaluate_at("3pm PST to CET", clock()),
+            Some("Sun, 18 Oct 2026 00:00 CEST".into())
+        );
+        assert_eq!(
+            evaluate_at("15:30 utc in tokyo", clock()),
+            Some("Sun, 18 Oct 2026 00:30 JST".into())
+        );
+        // "local" is the offset of the clock passed in
+        let paris = clock().with_timezone(&FixedOffset::east_opt(2 * 3600).unwrap());
+        assert_eq!(
+            evaluate_at("noon utc to local", paris),
+            Some("Sat, 17 Oct 2026 14:00".into())
+        );
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        assert_eq!(
+            evaluate_at("today + 45 days", clock()),
+            Some("Tue, 1 Dec 2026".into())
+        );
+        assert_eq!(
+            evaluate_at("2026-01-31 + 1 month", clock()),
+            Some("Sat, 28 Feb 2026".into())
+        );
+        assert_eq!(
+            evaluate_at("2026-01-31 - 1 month", clock()),
+            Some("Wed, 31 Dec 2025".into())
+        );
+        assert_eq!(
+            evaluate_at("now - 90 minutes", clock()),
+            Some("Sat, 17 Oct 2026 10:30".into())
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(evaluate_at("now + 999999999 days", clock()), None);
+        assert_eq!(evaluate_at("today + 4000000000 weeks", clock()), None);
+        assert_eq!(evaluate_at("today - 999999999 years", clock()), None);
+        assert_eq!(evaluate_at("unix -9223372036854775808", clock()), None);
+    }
+
+    #[test]
+    fn days_until() {
+        assert_eq!(evaluate_at("days until 2026-12-25", clock()), Some("69 days".into()));
+        assert_eq!(evaluate_at("days since 2026-10-16", clock()), Some("1 day".into()));
+    }
+
+    #[test]
+    fn unix_timestamps() {
+        assert_eq!(
+            evaluate_at("unix 1700000000", clock()),
+            Some("2023-11-14 22:13:20 UTC".into())
+        );
+        assert_eq!(
+            evaluate_at("unix 1700000000000", clock()),
+            Some("2023-11-14 22:13:20 UTC".into())
+        );
+        assert_eq!(evaluate_at("timestamp now", clock()), Some("1792238400".into()));
+    }
+
+    #[test]
+    fn parse_times() {
+        assert_eq!(parse_time("3pm"), NaiveTime::from_hms_opt(15, 0, 0));
+        assert_eq!(parse_time("12am"), NaiveTime::from_hms_opt(0, 0, 0));
+        assert_eq!(parse_time("9:45am"), NaiveTime::from_hms_opt(9, 45, 0));
+        assert_eq!(parse_time("13pm"), None);
+        assert_eq!(parse_time("15"), None);
+    }
+
+    #[test]
+    fn not_datetime() {
+        assert_eq!(evaluate_at("firefox", clock()), None);
+        assert_eq!(evaluate_at("go to bed", clock()), None);
+        assert_eq!(evaluate_at("2+3", clock()), None);
+        assert_eq!(evaluate_at("", clock()), None);
+    }
+}
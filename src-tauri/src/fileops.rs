@@ -0,0 +1,513 @@
+//! File operations on results under the home directory: move to the trash,
+//! rename, duplicate, and move or copy into another folder.
+
+use serde::ser::{SerializeStruct, Serializer};
+use serde::Serialize;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FileOpError {
+    #[error("Path must be absolute")]
+    NotAbsolute,
+    #[error("Path does not exist")]
+    NotFound,
+    #[error("Can only use paths under home directory")]
+    OutsideHome,
+    #[error("{} is not a folder", .0.display())]
+    NotAFolder(PathBuf),
+    #[error("{} already exists", .0.display())]
+    AlreadyExists(PathBuf),
+    #[error("Invalid name: {0:?}")]
+    InvalidName(String),
+    #[error("Cannot move or copy a folder into itself")]
+    IntoItself,
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl FileOpError {
+    fn kind(&self) -> &'static str {
+        match self {
+            FileOpError::NotAbsolute => "not_absolute",
+            FileOpError::NotFound => "not_found",
+            FileOpError::OutsideHome => "outside_home",
+            FileOpError::NotAFolder(_) => "not_a_folder",
+            FileOpError::AlreadyExists(_) => "already_exists",
+            FileOpError::InvalidName(_) => "invalid_name",
+            FileOpError::IntoItself => "into_itself",
+            FileOpError::Io(_) => "io",
+        }
+    }
+}
+
+/// Serialized as `{ kind, message }` so the frontend can tell errors apart.
+impl Serialize for FileOpError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut error = serializer.serialize_struct("FileOpError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+/// Resolve `path`, which must exist and lie under the home directory once
+/// symlinks are resolved.
+pub fn under_home(path: &Path) -> Result<PathBuf, FileOpError> {
+    if !path.is_absolute() {
+        return Err(FileOpError::NotAbsolute);
+    }
+    if !path.exists() {
+        return Err(FileOpError::NotFound);
+    }
+    let canonical = path.canonicalize()?;
+    let home = dirs::home_dir().ok_or(FileOpError::OutsideHome)?;
+    if !canonical.starts_with(home) {
+        return Err(FileOpError::OutsideHome);
+    }
+    Ok(canonical)
+}
+
+/// Like `under_home`, but a symlink stays a symlink: operations on it
+/// change the link, not what it points to. The home directory itself is
+/// never a valid entry.
+pub fn entry_under_home(path: &Path) -> Result<PathBuf, FileOpError> {
+    if !path.is_absolute() {
+        return Err(FileOpError::NotAbsolute);
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(FileOpError::OutsideHome);
+    };
+    let entry = under_home(parent)?.join(name);
+    if entry.symlink_metadata().is_err() {
+        return Err(FileOpError::NotFound);
+    }
+    Ok(entry)
+}
+
+/// Move `path` to the home trash, following the freedesktop Trash spec.
+/// Returns where it went.
+#[cfg(target_os = "linux")]
+pub fn trash(path: &Path) -> Result<PathBuf, FileOpError> {
+    let now = chrono::Local::now().naive_local();
+    trash_into(&crate::paths::trash_dir(), path, now)
+}
+
+/// Move `path` to the Finder's trash.
+#[cfg(target_os = "macos")]
+pub fn trash(path: &Path) -> Result<PathBuf, FileOpError> {
+    let script = format!(
+        "tell application \"Finder\" to delete POSIX file \"{}\"",
+        path.to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    );
+    let status = std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .status()?;
+    if !status.success() {
+        return Err(
+            io::Error::other(format!("cannot move {} to the trash", path.display())).into(),
+        );
+    }
+    Ok(dirs::home_dir().unwrap_or_default().join(".Trash"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn trash(_path: &Path) -> Result<PathBuf, FileOpError> {
+    Err(io::Error::from(io::ErrorKind::Unsupported).into())
+}
+
+/// Trash `path` into the trash directory `trash`. Files on another
+/// filesystem are copied there and then removed.
+#[cfg(any(target_os = "linux", test))]
+fn trash_into(
+    trash: &Path,
+    path: &Path,
+    now: chrono::NaiveDateTime,
+) -> Result<PathBuf, FileOpError> {
+    let files = trash.join("files");
+    let info = trash.join("info");
+    create_private_dir(&files)?;
+    create_private_dir(&info)?;
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| FileOpError::InvalidName(path.display().to_string()))?;
+    let is_dir = path.is_dir();
+    for n in 1.. {
+        let candidate = if n == 1 {
+            name.to_os_string()
+        } else {
+            numbered(name, &n.to_string(), is_dir)
+        };
+        let mut info_name = candidate.clone();
+        info_name.push(".trashinfo");
+        let info_file = info.join(info_name);
+
+        // Creating the info file first reserves the name
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_file)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let trashed = files.join(&candidate);
+        if trashed.symlink_metadata().is_ok() {
+            // Left behind without its info file; leave it alone
+            let _ = fs::remove_file(&info_file);
+            continue;
+        }
+
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            crate::actions::percent_encode(path),
+            now.format("%Y-%m-%dT%H:%M:%S")
+        );
+        let moved = file
+            .write_all(contents.as_bytes())
+            .and_then(|_| move_entry(path, &trashed));
+        if let Err(e) = moved {
+            let _ = fs::remove_file(&info_file);
+            return Err(e.into());
+        }
+        return Ok(trashed);
+    }
+    unreachable!()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+    }
+    #[cfg(not(unix))]
+    fs::create_dir_all(dir)
+}
+
+/// Rename `path` in place to `name`. Returns the new path.
+pub fn rename(path: &Path, name: &str) -> Result<PathBuf, FileOpError> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+        return Err(FileOpError::InvalidName(name.to_string()));
+    }
+    let target = path.with_file_name(name);
+    if target == path {
+        return Ok(target);
+    }
+    rename_no_replace(path, &target).map_err(|e| taken(e, &target))?;
+    Ok(target)
+}
+
+/// Copy `path` next to itself as "name copy", "name copy 2" and so on.
+/// Returns the copy's path.
+pub fn duplicate(path: &Path) -> Result<PathBuf, FileOpError> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(FileOpError::InvalidName(path.display().to_string()));
+    };
+    let is_dir = path.is_dir();
+    let target = (1..)
+        .map(|n| match n {
+            1 => dir.join(numbered(name, "copy", is_dir)),
+            n => dir.join(numbered(name, &format!("copy {}", n), is_dir)),
+        })
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap_or_default();
+    copy_entry(path, &target).map_err(|e| taken(e, &target))?;
+    Ok(target)
+}
+
+/// Move `path` into the folder `dir`. Returns the new path.
+pub fn move_to(path: &Path, dir: &Path) -> Result<PathBuf, FileOpError> {
+    let target = target_in(path, dir)?;
+    move_entry(path, &target).map_err(|e| taken(e, &target))?;
+    Ok(target)
+}
+
+/// Copy `path` into the folder `dir`. Returns the copy's path.
+pub fn copy_to(path: &Path, dir: &Path) -> Result<PathBuf, FileOpError> {
+    let target = target_in(path, dir)?;
+    copy_entry(path, &target).map_err(|e| taken(e, &target))?;
+    Ok(target)
+}
+
+/// Where `path` ends up in `dir`, which must be a folder that doesn't have
+/// an entry of that name and isn't inside `path`.
+fn target_in(path: &Path, dir: &Path) -> Result<PathBuf, FileOpError> {
+    if !dir.is_dir() {
+        return Err(FileOpError::NotAFolder(dir.to_path_buf()));
+    }
+    if dir.starts_with(path) {
+        return Err(FileOpError::IntoItself);
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| FileOpError::InvalidName(path.display().to_string()))?;
+    let target = dir.join(name);
+    ensure_free(&target)?;
+    Ok(target)
+}
+
+fn ensure_free(target: &Path) -> Result<(), FileOpError> {
+    match target.symlink_metadata() {
+        Ok(_) => Err(FileOpError::AlreadyExists(target.to_path_buf())),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Something appeared at `target` between checking and writing it.
+fn taken(e: io::Error, target: &Path) -> FileOpError {
+    if e.kind() == io::ErrorKind::AlreadyExists {
+        FileOpError::AlreadyExists(target.to_path_buf())
+    } else {
+        e.into()
+    }
+}
+
+/// "report.pdf" with `suffix` "copy" becomes "report copy.pdf". Folders and
+/// dotfiles keep their whole name in front.
+fn numbered(name: &OsStr, suffix: &str, is_dir: bool) -> OsString {
+    let name = name.to_string_lossy();
+    let split = match name.rfind('.') {
+        Some(dot) if dot > 0 && !is_dir => dot,
+        _ => name.len(),
+    };
+    format!("{} {}{}", &name[..split], suffix, &name[split..]).into()
+}
+
+/// Rename `from` to `to` without replacing anything already at `to`.
+fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = |path: &Path| {
+            CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+        };
+        let (from_c, to_c) = (c_path(from)?, c_path(to)?);
+        // SAFETY: both paths are NUL-terminated and outlive the call
+        let result = unsafe {
+            libc::renameat2(
+                libc::AT_FDCWD,
+                from_c.as_ptr(),
+                libc::AT_FDCWD,
+                to_c.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        // EINVAL: the filesystem doesn't support the flag
+        if error.raw_os_error() != Some(libc::EINVAL) {
+            return Err(error);
+        }
+    }
+
+    if fs::symlink_metadata(from)?.is_dir() {
+        // Folders can't be hard-linked; rename won't replace a non-empty one
+        if to.symlink_metadata().is_ok() {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        return fs::rename(from, to);
+    }
+    // Linking fails when `to` exists, where a rename would replace it
+    fs::hard_link(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Rename, or copy and remove when `to` is on another filesystem. Never
+/// replaces anything at `to`.
+fn move_entry(from: &Path, to: &Path) -> io::Result<()> {
+    match rename_no_replace(from, to) {
+        Err(e) if e.raw_os_error() == Some(libc::EXDEV) => {
+            copy_entry(from, to)?;
+            if from.symlink_metadata()?.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Copy a file, symlink or whole folder, never overwriting anything.
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.file_type().is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+    }
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // Last, in case the folder is read-only
+        return fs::set_permissions(to, meta.permissions());
+    }
+    let mut source = File::open(from)?;
+    let mut copy = OpenOptions::new().write(true).create_new(true).open(to)?;
+    io::copy(&mut source, &mut copy)?;
+    copy.set_permissions(meta.permissions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cheru-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_trash_writes_trashinfo() {
+        let dir = temp_dir("trash");
+        let trash = dir.join("Trash");
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 3, 9)
+            .unwrap()
+            .and_hms_opt(14, 5, 0)
+            .unwrap();
+
+        for _ in 0..2 {
+            fs::write(dir.join("old notes.txt"), "x").unwrap();
+            trash_into(&trash, &dir.join("old notes.txt"), now).unwrap();
+        }
+        assert!(!dir.join("old notes.txt").exists());
+        assert!(trash.join("files/old notes.txt").exists());
+        assert!(trash.join("files/old notes 2.txt").exists());
+
+        let info = fs::read_to_string(trash.join("info/old notes 2.txt.trashinfo")).unwrap();
+        let path = crate::actions::percent_encode(&dir.join("old notes.txt"));
+        assert_eq!(
+            info,
+            format!(
+                "[Trash Info]\nPath={}\nDeletionDate=2024-03-09T14:05:00\n",
+                path
+            )
+        );
+        assert!(path.ends_with("/old%20notes.txt"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rename() {
+        let dir = temp_dir("rename");
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+
+        assert!(matches!(
+            rename(&dir.join("a.txt"), "../a.txt"),
+            Err(FileOpError::InvalidName(_))
+        ));
+        assert!(matches!(
+            rename(&dir.join("a.txt"), "b.txt"),
+            Err(FileOpError::AlreadyExists(_))
+        ));
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "b");
+        // A plain rename would replace the empty folder
+        fs::create_dir(dir.join("x")).unwrap();
+        fs::create_dir(dir.join("y")).unwrap();
+        assert!(matches!(
+            rename(&dir.join("x"), "y"),
+            Err(FileOpError::AlreadyExists(_))
+        ));
+        assert_eq!(
+            rename(&dir.join("a.txt"), "c.txt").unwrap(),
+            dir.join("c.txt")
+        );
+        assert_eq!(fs::read_to_string(dir.join("c.txt")).unwrap(), "a");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let dir = temp_dir("duplicate");
+        fs::create_dir_all(dir.join("site.v2/css")).unwrap();
+        fs::write(dir.join("site.v2/css/main.css"), "body {}").unwrap();
+        fs::write(dir.join("report.pdf"), "pdf").unwrap();
+
+        assert_eq!(
+            duplicate(&dir.join("report.pdf")).unwrap(),
+            dir.join("report copy.pdf")
+        );
+        assert_eq!(
+            duplicate(&dir.join("report.pdf")).unwrap(),
+            dir.join("report copy 2.pdf")
+        );
+        let copy = duplicate(&dir.join("site.v2")).unwrap();
+        assert_eq!(copy, dir.join("site.v2 copy"));
+        assert_eq!(
+            fs::read_to_string(copy.join("css/main.css")).unwrap(),
+            "body {}"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_move_and_copy_to_folder() {
+        let dir = temp_dir("transfer");
+        fs::create_dir_all(dir.join("Downloads/album")).unwrap();
+        fs::create_dir_all(dir.join("Music")).unwrap();
+        fs::write(dir.join("Downloads/album/track.ogg"), "ogg").unwrap();
+
+        let album = dir.join("Downloads/album");
+        assert!(matches!(
+            copy_to(&album, &album),
+            Err(FileOpError::IntoItself)
+        ));
+        assert!(matches!(
+            move_to(&album, &dir.join("Downloads/album/track.ogg")),
+            Err(FileOpError::NotAFolder(_))
+        ));
+
+        let copied = copy_to(&album, &dir.join("Music")).unwrap();
+        assert!(copied.join("track.ogg").exists());
+        assert!(matches!(
+            move_to(&album, &dir.join("Music")),
+            Err(FileOpError::AlreadyExists(_))
+        ));
+        fs::remove_dir_all(&copied).unwrap();
+        let moved = move_to(&album, &dir.join("Music")).unwrap();
+        assert!(moved.join("track.ogg").exists());
+        assert!(!album.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let error = FileOpError::AlreadyExists(PathBuf::from("/home/me/a.txt"));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "already_exists",
+                "message": "/home/me/a.txt already exists"
+            })
+        );
+    }
+}
@@ -2,12 +2,65 @@ use super::{AppEntry, ResultType};
 use freedesktop_desktop_entry::{DesktopEntry, Iter as DesktopIter};
 use std::collections::HashSet;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn index_apps() -> Vec<AppEntry> {
+/// Where an app's desktop entry was installed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Native,
+    Flatpak,
+    Snap,
+    Nix,
+}
+
+impl Source {
+    /// Shown in front of the app's description; native apps have none.
+    fn badge(self) -> Option<&'static str> {
+        match self {
+            Source::Native => None,
+            Source::Flatpak => Some("Flatpak"),
+            Source::Snap => Some("Snap"),
+            Source::Nix => Some("Nix"),
+        }
+    }
+}
+
+/// Application folders packaging tools export to, searched even when
+/// `$XDG_DATA_DIRS` doesn't list them. `~/` is the home directory.
+const EXPORT_DIRS: &[&str] = &[
+    "~/.local/share/flatpak/exports/share/applications",
+    "/var/lib/flatpak/exports/share/applications",
+    "/var/lib/snapd/desktop/applications",
+    "~/.nix-profile/share/applications",
+    "/run/current-system/sw/share/applications",
+];
+
+/// An application's desktop entry, with what launching and Open With need.
+pub struct DesktopApp {
+    /// Desktop file ID, as `mimeapps.list` refers to it
+    pub id: String,
+    pub path: PathBuf,
+    pub entry: AppEntry,
+    /// `MimeType=` types the app can open
+    pub mime_types: Vec<String>,
+    /// `NoDisplay=true`: a valid handler, but not listed as an app
+    pub no_display: bool,
+    pub source: Source,
+}
+
+/// Every application desktop entry, once per ID and source. Earlier data
+/// directories shadow later ones, as the spec requires, but a native app
+/// doesn't hide the Flatpak or Snap with the same ID.
+pub fn desktop_apps() -> Vec<DesktopApp> {
     let mut apps = Vec::new();
     let mut seen = HashSet::new();
+    let mut hidden = HashSet::new();
 
-    for path in DesktopIter::new(freedesktop_desktop_entry::default_paths()) {
+    for path in DesktopIter::new(search_dirs()) {
+        let id = desktop_id(&path);
+        if hidden.contains(&id) {
+            continue;
+        }
         if let Ok(content) = fs::read_to_string(&path) {
             if let Ok(entry) = DesktopEntry::from_str(&path, &content, Some(&["en"])) {
                 // Skip non-application types
@@ -15,8 +68,9 @@ pub fn index_apps() -> Vec<AppEntry> {
                     continue;
                 }
 
-                // Skip hidden and no-display entries
-                if entry.no_display() || entry.hidden() {
+                // Hidden means deleted, whichever source the app is from
+                if entry.hidden() {
+                    hidden.insert(id);
                     continue;
                 }
 
@@ -25,31 +79,176 @@ pub fn index_apps() -> Vec<AppEntry> {
                     None => continue,
                 };
 
-                // Deduplicate by name
-                if !seen.insert(name.clone()) {
-                    continue;
-                }
-
                 let exec = match entry.exec() {
                     Some(e) => e.to_string(),
                     None => continue,
                 };
 
-                apps.push(AppEntry {
-                    name,
-                    exec,
-                    icon: entry.icon().map(|s| s.to_string()),
-                    description: entry.comment(&["en"]).map(|s| s.to_string()),
-                    result_type: ResultType::App,
+                let mime_types = entry
+                    .desktop_entry("MimeType")
+                    .unwrap_or_default()
+                    .split(';')
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect();
+
+                let source = detect_source(&path, &exec);
+                if !seen.insert((id.clone(), source)) {
+                    continue;
+                }
+                let comment = entry.comment(&["en"]).map(|s| s.to_string());
+                let description = match (source.badge(), comment) {
+                    (Some(badge), Some(comment)) => Some(format!("{} · {}", badge, comment)),
+                    (Some(badge), None) => Some(badge.to_string()),
+                    (None, comment) => comment,
+                };
+
+                apps.push(DesktopApp {
+                    id,
+                    no_display: entry.no_display(),
+                    mime_types,
+                    source,
+                    entry: AppEntry {
+                        name,
+                        exec,
+                        icon: entry.icon().map(|icon| resolve_icon(icon, &path, source)),
+                        description,
+                        result_type: ResultType::App,
+                    },
+                    path,
                 });
             }
         }
     }
 
+    apps
+}
+
+pub fn index_apps() -> Vec<AppEntry> {
+    let mut seen = HashSet::new();
+    let mut apps: Vec<AppEntry> = desktop_apps()
+        .into_iter()
+        // Skip no-display entries
+        .filter(|app| !app.no_display)
+        // Deduplicate by name, keeping e.g. both the native and Flatpak
+        // variants of an app
+        .filter(|app| seen.insert((app.entry.name.clone(), app.source)))
+        .map(|app| app.entry)
+        .collect();
+
     apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
     apps
 }
 
+/// Find the desktop entry `index_apps` took the app running `exec` from.
+/// The first match wins, as with indexing.
+pub fn desktop_file(exec: &str) -> Option<PathBuf> {
+    desktop_apps()
+        .into_iter()
+        .find(|app| !app.no_display && app.entry.exec == exec)
+        .map(|app| app.path)
+}
+
+/// The XDG application folders, then any export folders they miss.
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = freedesktop_desktop_entry::default_paths()
+        .into_iter()
+        .collect();
+    let home = dirs::home_dir().unwrap_or_default();
+    for dir in EXPORT_DIRS {
+        let dir = match dir.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(dir),
+        };
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// Tell packaged apps apart by where their entry lives and how it runs.
+fn detect_source(path: &Path, exec: &str) -> Source {
+    let program = exec.split_whitespace().next().unwrap_or_default();
+    if path.components().any(|c| c.as_os_str() == "flatpak")
+        || (program.ends_with("flatpak") && exec.contains(" run "))
+    {
+        Source::Flatpak
+    } else if path.starts_with("/var/lib/snapd") || exec.contains("/snap/bin/") {
+        Source::Snap
+    } else if path.starts_with("/nix/store")
+        || path.starts_with("/run/current-system")
+        || path.to_string_lossy().contains("/.nix-profile/")
+        || program.starts_with("/nix/store/")
+    {
+        Source::Nix
+    } else {
+        Source::Native
+    }
+}
+
+/// Icon sizes to look for in a Flatpak export, best first.
+const FLATPAK_ICON_DIRS: &[&str] = &[
+    "scalable/apps",
+    "512x512/apps",
+    "256x256/apps",
+    "128x128/apps",
+    "64x64/apps",
+    "48x48/apps",
+];
+
+/// Flatpak icons are theme names installed only in the export's own icon
+/// folder, so point at the file. Snap icons name a revision that goes away
+/// on the next refresh.
+fn resolve_icon(icon: &str, desktop_file: &Path, source: Source) -> String {
+    let resolved = match source {
+        Source::Flatpak => flatpak_icon(icon, desktop_file),
+        Source::Snap => snap_current(icon),
+        Source::Native | Source::Nix => None,
+    };
+    resolved.unwrap_or_else(|| icon.to_string())
+}
+
+/// `exports/share/icons/hicolor/<size>/apps/<icon>` next to
+/// `exports/share/applications/<desktop file>`.
+fn flatpak_icon(icon: &str, desktop_file: &Path) -> Option<String> {
+    if icon.starts_with('/') {
+        return None;
+    }
+    let hicolor = desktop_file.parent()?.parent()?.join("icons/hicolor");
+    FLATPAK_ICON_DIRS
+        .iter()
+        .flat_map(|size| {
+            ["svg", "png"].map(|ext| hicolor.join(size).join(format!("{}.{}", icon, ext)))
+        })
+        .find(|file| file.is_file())
+        .map(|file| file.to_string_lossy().to_string())
+}
+
+/// `/snap/<name>/<revision>/…` to `/snap/<name>/current/…`.
+fn snap_current(icon: &str) -> Option<String> {
+    let mut parts = icon.strip_prefix("/snap/")?.splitn(3, '/');
+    let (name, revision, rest) = (parts.next()?, parts.next()?, parts.next()?);
+    // Locally installed snaps are numbered x1, x2, …
+    let number = revision.strip_prefix('x').unwrap_or(revision);
+    (!number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| format!("/snap/{}/current/{}", name, rest))
+}
+
+/// The desktop file ID: the path below `applications/`, with `/` turned
+/// into `-`.
+fn desktop_id(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let start = parts
+        .iter()
+        .rposition(|c| c == "applications")
+        .map_or(parts.len().saturating_sub(1), |i| i + 1);
+    parts[start..].join("-")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,6 +262,90 @@ mod tests {
         assert!(apps.iter().all(|a| !a.exec.is_empty()));
     }
 
+    #[test]
+    fn test_desktop_id() {
+        assert_eq!(
+            desktop_id(Path::new(
+                "/usr/share/applications/org.gnome.Nautilus.desktop"
+            )),
+            "org.gnome.Nautilus.desktop"
+        );
+        assert_eq!(
+            desktop_id(Path::new(
+                "/home/me/.local/share/applications/kde4/kate.desktop"
+            )),
+            "kde4-kate.desktop"
+        );
+    }
+
+    #[test]
+    fn test_detect_source() {
+        let source = |path: &str, exec: &str| detect_source(Path::new(path), exec);
+        assert_eq!(
+            source(
+                "/var/lib/flatpak/exports/share/applications/org.mozilla.firefox.desktop",
+                "/usr/bin/flatpak run --branch=stable --command=firefox org.mozilla.firefox @@u %u @@"
+            ),
+            Source::Flatpak
+        );
+        assert_eq!(
+            source(
+                "/var/lib/snapd/desktop/applications/firefox_firefox.desktop",
+                "env BAMF_DESKTOP_FILE_HINT=/var/lib/snapd/desktop/applications/firefox_firefox.desktop /snap/bin/firefox %u"
+            ),
+            Source::Snap
+        );
+        assert_eq!(
+            source(
+                "/home/me/.nix-profile/share/applications/firefox.desktop",
+                "firefox %U"
+            ),
+            Source::Nix
+        );
+        assert_eq!(
+            source("/usr/share/applications/firefox.desktop", "firefox %u"),
+            Source::Native
+        );
+    }
+
+    #[test]
+    fn test_resolve_icon() {
+        assert_eq!(
+            resolve_icon(
+                "/snap/firefox/5091/default256.png",
+                Path::new("/var/lib/snapd/desktop/applications/firefox_firefox.desktop"),
+                Source::Snap
+            ),
+            "/snap/firefox/current/default256.png"
+        );
+
+        let dir = std::env::temp_dir().join(format!("cheru-test-flatpak-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let share = dir.join("exports/share");
+        fs::create_dir_all(share.join("applications")).unwrap();
+        fs::create_dir_all(share.join("icons/hicolor/128x128/apps")).unwrap();
+        fs::write(
+            share.join("icons/hicolor/128x128/apps/org.gimp.GIMP.png"),
+            "",
+        )
+        .unwrap();
+        let desktop = share.join("applications/org.gimp.GIMP.desktop");
+
+        assert_eq!(
+            resolve_icon("org.gimp.GIMP", &desktop, Source::Flatpak),
+            share
+                .join("icons/hicolor/128x128/apps/org.gimp.GIMP.png")
+                .to_string_lossy()
+        );
+        assert_eq!(
+            resolve_icon("missing", &desktop, Source::Flatpak),
+            "missing"
+        );
+        assert_eq!(resolve_icon("firefox", &desktop, Source::Native), "firefox");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_index_apps_sorted() {
         let apps = index_apps();
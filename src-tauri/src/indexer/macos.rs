@@ -45,7 +45,7 @@ fn get_search_dirs() -> Vec<PathBuf> {
 }
 
 fn convert_icon_to_png(icns_path: &str, app_name: &str) -> Option<String> {
-    let cache_dir = dirs::home_dir()?.join(".cache/cheru/icons");
+    let cache_dir = crate::paths::cache_dir().join("icons");
     std::fs::create_dir_all(&cache_dir).ok()?;
 
     // Use a sanitized filename
@@ -7,6 +7,17 @@ pub enum ResultType {
     Image,
     System,
     File,
+    Calculator,
+    /// An executable on `$PATH`
+    Command,
+    /// A `$` shell command line
+    Shell,
+    /// A script command from the scripts folder
+    Script,
+    /// A URL to open: a quicklink, or a search filled in with the query
+    WebSearch,
+    /// A quicklink that needs search terms
+    Quicklink,
 }
 
 const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg"];
@@ -25,6 +36,9 @@ pub struct AppEntry {
 #[cfg(target_os = "linux")]
 mod linux;
 
+#[cfg(target_os = "linux")]
+pub use linux::{desktop_apps, DesktopApp};
+
 #[cfg(target_os = "macos")]
 pub mod macos;
 
@@ -43,6 +57,12 @@ pub fn build_index() -> Vec<AppEntry> {
     }
 }
 
+/// The `.desktop` file the indexed app running `exec` was read from.
+#[cfg(target_os = "linux")]
+pub fn desktop_file(exec: &str) -> Option<std::path::PathBuf> {
+    linux::desktop_file(exec)
+}
+
 pub fn build_folder_index() -> Vec<AppEntry> {
     let mut folders = Vec::new();
     let mut seen = std::collections::HashSet::new();
@@ -99,6 +119,47 @@ pub fn build_image_index() -> Vec<AppEntry> {
     images
 }
 
+/// Follow a file operation in a folder or image index: entries at or under
+/// `from` move to `to`, or are dropped when `to` is `None`.
+pub fn relocate(entries: &mut Vec<AppEntry>, from: &std::path::Path, to: Option<&std::path::Path>) {
+    let Some(to) = to else {
+        entries.retain(|e| !std::path::Path::new(&e.exec).starts_with(from));
+        return;
+    };
+    for entry in entries.iter_mut() {
+        let Ok(rest) = std::path::Path::new(&entry.exec).strip_prefix(from) else {
+            continue;
+        };
+        // Joining an empty `rest` would add a trailing slash
+        let path = if rest.as_os_str().is_empty() {
+            to.to_path_buf()
+        } else {
+            to.join(rest)
+        };
+        if let Some(name) = path.file_name() {
+            entry.name = name.to_string_lossy().to_string();
+        }
+        entry.description = path.parent().map(|p| p.to_string_lossy().to_string());
+        entry.exec = path.to_string_lossy().to_string();
+        if entry.icon.is_some() {
+            // Images are their own icon
+            entry.icon = Some(entry.exec.clone());
+        }
+    }
+    entries.sort_by_key(|e| e.name.to_lowercase());
+}
+
+/// Executables in the `$PATH` folders. A name is listed once, from the
+/// folder that comes first, since that is the one a shell would run.
+pub fn build_command_index() -> Vec<AppEntry> {
+    let dirs: Vec<std::path::PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    let mut commands = collect_commands(&dirs);
+    commands.sort_by_key(|c| c.name.to_lowercase());
+    commands
+}
+
 pub fn build_system_commands() -> Vec<AppEntry> {
     let mut cmds = Vec::new();
 
@@ -144,9 +205,71 @@ pub fn build_system_commands() -> Vec<AppEntry> {
         }
     }
 
+    // Cheru's own settings; the frontend lists choices for the key after `settings:`
+    let settings = [
+        ("Cheru: Change Theme", "settings:theme", "Pick a color theme"),
+        ("Cheru: Start on Login", "settings:autostart", "Turn autostart on or off"),
+    ];
+    for (name, exec, desc) in settings {
+        cmds.push(AppEntry {
+            name: name.to_string(),
+            exec: exec.to_string(),
+            icon: None,
+            description: Some(desc.to_string()),
+            result_type: ResultType::System,
+        });
+    }
+
     cmds
 }
 
+fn collect_commands(dirs: &[std::path::PathBuf]) -> Vec<AppEntry> {
+    let mut commands = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    for dir in dirs {
+        // Relative entries depend on the working directory; skip them
+        if !dir.is_absolute() {
+            continue;
+        }
+        let entries = match std::fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(n) if !n.starts_with('.') => n.to_string(),
+                _ => continue,
+            };
+            if !is_executable(&path) || !seen.insert(name.clone()) {
+                continue;
+            }
+            commands.push(AppEntry {
+                name,
+                exec: path.to_string_lossy().to_string(),
+                icon: None,
+                description: Some(dir.to_string_lossy().to_string()),
+                result_type: ResultType::Command,
+            });
+        }
+    }
+
+    commands
+}
+
+/// A file, or a link to one, that anyone may execute.
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
 fn collect_images(
     dir: &std::path::Path,
     depth: usize,
@@ -302,3 +425,85 @@ fn collect_folders(
         collect_folders(&path, depth + 1, max_depth, folders, seen);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_commands() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cheru-test-path-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        let write = |path: std::path::PathBuf, mode: u32| {
+            std::fs::write(&path, "").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        write(first.join("htop"), 0o755);
+        write(first.join("notes.txt"), 0o644);
+        write(second.join("htop"), 0o755);
+        write(second.join("rsync"), 0o700);
+
+        let mut commands = collect_commands(&[first.clone(), "bin".into(), second.clone()]);
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        let found: Vec<(&str, &str)> = commands
+            .iter()
+            .map(|c| (c.name.as_str(), c.exec.as_str()))
+            .collect();
+        let htop = first.join("htop").to_string_lossy().to_string();
+        let rsync = second.join("rsync").to_string_lossy().to_string();
+        assert_eq!(found, [("htop", htop.as_str()), ("rsync", rsync.as_str())]);
+        assert!(commands
+            .iter()
+            .all(|c| c.result_type == ResultType::Command));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn entry(path: &str) -> AppEntry {
+        AppEntry {
+            name: path.rsplit('/').next().unwrap().to_string(),
+            exec: path.to_string(),
+            icon: None,
+            description: None,
+            result_type: ResultType::Folder,
+        }
+    }
+
+    #[test]
+    fn test_relocate() {
+        use std::path::Path;
+
+        let mut entries = vec![
+            entry("/home/u/Code"),
+            entry("/home/u/Code/app"),
+            entry("/home/u/Codex"),
+        ];
+        relocate(
+            &mut entries,
+            Path::new("/home/u/Code"),
+            Some(Path::new("/home/u/work")),
+        );
+        let found: Vec<(&str, &str, Option<&str>)> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.exec.as_str(), e.description.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("app", "/home/u/work/app", Some("/home/u/work")),
+                ("Codex", "/home/u/Codex", None),
+                ("work", "/home/u/work", Some("/home/u")),
+            ]
+        );
+
+        relocate(&mut entries, Path::new("/home/u/work"), None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].exec, "/home/u/Codex");
+    }
+}
@@ -1,17 +1,40 @@
+mod actions;
+mod allowlist;
+mod calc_history;
 mod calculator;
+mod cli;
+mod cmdline;
 mod commands;
 mod config;
+#[cfg(unix)]
+mod control;
+mod datetime;
+mod fileops;
 mod indexer;
 mod matcher;
+#[cfg(target_os = "linux")]
+mod mime;
+#[cfg(target_os = "linux")]
+mod open_with;
+mod paths;
+#[cfg(target_os = "linux")]
+mod portal;
+mod scripts;
+mod shell;
+mod themes;
+mod web;
 
 use commands::AppState;
 use matcher::FuzzyMatcher;
-use std::sync::{Mutex, OnceLock, RwLock};
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+use std::sync::{Mutex, RwLock};
 use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder},
+    menu::{MenuBuilder, MenuItem, MenuItemBuilder},
     tray::TrayIconBuilder,
-    Manager,
+    AppHandle, Emitter, Manager,
 };
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
 fn setup_autostart(enabled: bool) {
     #[cfg(target_os = "macos")]
@@ -49,9 +72,7 @@ fn setup_autostart(enabled: bool) {
 
     #[cfg(target_os = "linux")]
     {
-        let autostart_dir = dirs::config_dir()
-            .unwrap_or_else(|| dirs::home_dir().unwrap_or_default().join(".config"))
-            .join("autostart");
+        let autostart_dir = paths::autostart_dir();
         let desktop_path = autostart_dir.join("cheru.desktop");
 
         if enabled {
@@ -64,29 +85,481 @@ fn setup_autostart(enabled: bool) {
     }
 }
 
+fn calc_format(cfg: &config::Config) -> calculator::Format {
+    calculator::Format::new(
+        cfg.calculator.precision,
+        &cfg.calculator.locale,
+        cfg.calculator.thousands_separator,
+    )
+}
+
+fn toggle_launcher(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("launcher") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+            let _ = window.center();
+        }
+    }
+}
+
+/// Show the launcher with `query` typed in. The frontend receives it as a
+/// `launcher-query` event, sent before the window gains focus.
+fn show_launcher_with(app: &AppHandle, query: &str) {
+    let _ = app.emit("launcher-query", query);
+    if let Some(window) = app.get_webview_window("launcher") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.center();
+    }
+}
+
+/// What a hotkey does: `None` toggles, a query opens the launcher with it.
+fn on_hotkey(app: &AppHandle, query: Option<&str>) {
+    match query {
+        Some(q) => show_launcher_with(app, q),
+        None => toggle_launcher(app),
+    }
+}
+
+/// Register every binding with the global-shortcut plugin. Only a failure of
+/// the toggle binding is an error; mode bindings that another app holds are
+/// skipped with a warning.
+fn register_hotkeys(
+    app: &AppHandle,
+    hotkeys: &[config::Hotkey],
+) -> Result<(), tauri_plugin_global_shortcut::Error> {
+    for hotkey in hotkeys {
+        let query = hotkey.query.clone();
+        let result = app
+            .global_shortcut()
+            .on_shortcut(hotkey.key.as_str(), move |app, _shortcut, event| {
+                if event.state == ShortcutState::Pressed {
+                    on_hotkey(app, query.as_deref());
+                }
+            });
+        match result {
+            Err(e) if hotkey.query.is_some() => {
+                eprintln!("Warning: cannot register {} hotkey {:?}: {}", hotkey.mode, hotkey.key, e)
+            }
+            other => other?,
+        }
+    }
+    Ok(())
+}
+
+/// Which mechanism delivers global shortcuts, shown in the tray menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShortcutBackend {
+    /// The global-shortcut plugin, which uses X11 grabs on Linux
+    Plugin,
+    /// The XDG GlobalShortcuts portal, on Wayland
+    #[cfg(target_os = "linux")]
+    Portal,
+    /// Not even the toggle binding could be registered
+    Unavailable,
+}
+
+impl ShortcutBackend {
+    fn label(self) -> &'static str {
+        match self {
+            #[cfg(target_os = "linux")]
+            ShortcutBackend::Plugin => "Shortcuts: X11 grab",
+            #[cfg(not(target_os = "linux"))]
+            ShortcutBackend::Plugin => "Shortcuts: system",
+            #[cfg(target_os = "linux")]
+            ShortcutBackend::Portal => "Shortcuts: XDG portal",
+            ShortcutBackend::Unavailable => "Shortcuts: unavailable",
+        }
+    }
+}
+
+/// Managed next to `AppState`; owns whatever keeps the shortcuts alive.
+struct Shortcuts {
+    /// Disabled tray menu entry naming the active backend
+    status: MenuItem<tauri::Wry>,
+    /// Open portal session and the task listening for its activations
+    #[cfg(target_os = "linux")]
+    portal: tauri::async_runtime::Mutex<
+        Option<(portal::Session, tauri::async_runtime::JoinHandle<()>)>,
+    >,
+    /// The hotkeys actually bound, which may not be the configured ones
+    bound: Mutex<Vec<config::Hotkey>>,
+}
+
+fn set_shortcut_backend(app: &AppHandle, backend: ShortcutBackend, bound: &[config::Hotkey]) {
+    if let Some(shortcuts) = app.try_state::<Shortcuts>() {
+        let _ = shortcuts.status.set_text(backend.label());
+        *shortcuts.bound.lock().unwrap_or_else(|e| e.into_inner()) = bound.to_vec();
+    }
+}
+
+fn bound_hotkeys(app: &AppHandle) -> Vec<config::Hotkey> {
+    app.try_state::<Shortcuts>()
+        .map(|shortcuts| {
+            shortcuts
+                .bound
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        })
+        .unwrap_or_default()
+}
+
+/// Bind `hotkeys` through the portal on Wayland, where X11 grabs never fire,
+/// and through the plugin otherwise or if the portal is missing.
+/// The bound hotkeys are restored if the plugin can't take the new ones.
+fn bind_hotkeys(app: &AppHandle, hotkeys: &[config::Hotkey]) {
+    #[cfg(target_os = "linux")]
+    if portal::is_wayland() {
+        let app = app.clone();
+        let hotkeys = hotkeys.to_vec();
+        // The desktop may ask the user to confirm, so don't block on it
+        tauri::async_runtime::spawn(async move {
+            match bind_portal(&app, &hotkeys).await {
+                Ok(()) => {
+                    let _ = app.global_shortcut().unregister_all();
+                    set_shortcut_backend(&app, ShortcutBackend::Portal, &hotkeys);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Warning: shortcuts portal unavailable, using X11 grabs: {}",
+                        e
+                    );
+                    bind_plugin(&app, &hotkeys);
+                }
+            }
+        });
+        return;
+    }
+    bind_plugin(app, hotkeys);
+}
+
+fn bind_plugin(app: &AppHandle, hotkeys: &[config::Hotkey]) {
+    let previous = bound_hotkeys(app);
+    let _ = app.global_shortcut().unregister_all();
+    match register_hotkeys(app, hotkeys) {
+        Ok(()) => set_shortcut_backend(app, ShortcutBackend::Plugin, hotkeys),
+        Err(e) => {
+            eprintln!("Warning: cannot register hotkeys: {}", e);
+            // Keep the launcher reachable with the previous bindings
+            let _ = app.global_shortcut().unregister_all();
+            if !previous.is_empty() && register_hotkeys(app, &previous).is_ok() {
+                set_shortcut_backend(app, ShortcutBackend::Plugin, &previous);
+            } else {
+                set_shortcut_backend(app, ShortcutBackend::Unavailable, &[]);
+            }
+        }
+    }
+}
+
+/// Replace the portal session with one binding `hotkeys`.
+#[cfg(target_os = "linux")]
+async fn bind_portal(app: &AppHandle, hotkeys: &[config::Hotkey]) -> Result<(), ashpd::Error> {
+    use futures_util::StreamExt;
+
+    let shortcuts = app.state::<Shortcuts>();
+    let mut current = shortcuts.portal.lock().await;
+    if let Some((session, listener)) = current.take() {
+        listener.abort();
+        session.close().await;
+    }
+
+    let session = portal::bind(hotkeys).await?;
+    let mut activations = session.activations().await?;
+    let queries: std::collections::HashMap<String, Option<String>> = hotkeys
+        .iter()
+        .map(|h| (h.mode.clone(), h.query.clone()))
+        .collect();
+    let app = app.clone();
+    let listener = tauri::async_runtime::spawn(async move {
+        while let Some(mode) = activations.next().await {
+            if let Some(query) = queries.get(&mode) {
+                on_hotkey(&app, query.as_deref());
+            }
+        }
+    });
+    *current = Some((session, listener));
+    Ok(())
+}
+
+/// Re-read config.toml and apply whatever changed to the running app.
+pub(crate) fn reload_config(app: &AppHandle) {
+    let (cfg, diagnostics) = config::load();
+    let state = app.state::<AppState>();
+    *state
+        .config_diagnostics
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = diagnostics;
+    // Compare against a copy so binding doesn't block readers of the config
+    let current = state
+        .config
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+
+    if cfg.hotkeys != bound_hotkeys(app) {
+        bind_hotkeys(app, &cfg.hotkeys);
+    }
+
+    if cfg.autostart != current.autostart {
+        setup_autostart(cfg.autostart);
+    }
+
+    if cfg.calculator != current.calculator {
+        state
+            .calc
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_format(calc_format(&cfg));
+    }
+
+    let _ = app.emit("config-changed", cfg.theme_config());
+    *state.config.write().unwrap_or_else(|e| e.into_inner()) = cfg;
+}
+
+fn build_app_index() -> Vec<indexer::AppEntry> {
+    let mut index = indexer::build_index();
+    index.extend(indexer::build_system_commands());
+    index
+}
+
+/// Rescan installed applications in the background and swap in the result.
+fn reindex(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let index = build_app_index();
+        // Convert before swapping so searches aren't blocked meanwhile
+        #[cfg(target_os = "macos")]
+        let index = {
+            let mut index = index;
+            indexer::macos::convert_icons(&mut index);
+            index
+        };
+        println!("Reindexed {} applications", index.len());
+        let state = app.state::<AppState>();
+        *state.index.write().unwrap_or_else(|e| e.into_inner()) = index;
+        // Programs may have come or gone along with the apps. Until commands
+        // are first searched there is no list to refresh.
+        let listed = state
+            .command_index
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some();
+        if listed {
+            let commands = indexer::build_command_index();
+            *state
+                .command_index
+                .write()
+                .unwrap_or_else(|e| e.into_inner()) = Some(commands);
+        }
+    });
+}
+
+/// Carry out a CLI command given to this process or forwarded from a second
+/// `cheru` invocation.
+fn handle_command(app: &AppHandle, command: cli::Command) {
+    match command {
+        cli::Command::Run => show_launcher_with(app, ""),
+        cli::Command::Show { query } => show_launcher_with(app, query.as_deref().unwrap_or("")),
+        cli::Command::Toggle => toggle_launcher(app),
+        cli::Command::Reindex => reindex(app),
+        cli::Command::Quit => app.exit(0),
+        // Handled before the app starts
+        cli::Command::Search { .. }
+        | cli::Command::CheckConfig
+        | cli::Command::Help
+        | cli::Command::Version => {}
+    }
+}
+
+/// Results for a query across apps, folders and images, as the launcher's
+/// normal search mode shows them.
+#[cfg(unix)]
+fn search_all(app: &AppHandle, query: &str) -> Vec<commands::AppResult> {
+    let mut results = commands::search_apps(query.to_string(), app.state());
+    results.extend(commands::search_folders(query.to_string(), app.state()));
+    results.extend(commands::search_images(query.to_string(), app.state()));
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|r| seen.insert(r.exec.clone()));
+    results
+}
+
+/// Launch a search result by its `exec`, with the same checks as the UI.
+#[cfg(unix)]
+fn launch_by_id(app: &AppHandle, id: &str) -> Result<(), commands::CommandError> {
+    if let Some(system) = id.strip_prefix("system:") {
+        return commands::run_system_command(system.to_string());
+    }
+    let state = app.state::<AppState>();
+    let is_app = state
+        .index
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|e| e.exec == id && e.result_type == indexer::ResultType::App);
+    if is_app {
+        commands::launch_app(id.to_string(), app.state())
+    } else {
+        commands::open_path(id.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn handle_control(
+    app: &AppHandle,
+    request: control::Request,
+) -> Result<serde_json::Value, String> {
+    use control::Request;
+    match request {
+        Request::Show { query } => show_launcher_with(app, query.as_deref().unwrap_or("")),
+        Request::Hide => {
+            commands::hide_launcher_window(app.clone()).map_err(|e| e.to_string())?
+        }
+        Request::Toggle => toggle_launcher(app),
+        Request::Search { query } => {
+            return serde_json::to_value(search_all(app, &query)).map_err(|e| e.to_string())
+        }
+        Request::Launch { id } => launch_by_id(app, &id).map_err(|e| e.to_string())?,
+        Request::Reindex => reindex(app),
+    }
+    Ok(serde_json::Value::Null)
+}
+
+/// `cheru search`: build the indexes and rank results without starting the
+/// app. Returns the exit code: 1 when nothing matched.
+fn search_headless(query: &str, kind: Option<cli::SearchKind>, json: bool) -> i32 {
+    use cli::SearchKind;
+
+    let mut matcher = FuzzyMatcher::new();
+    let path_query = query.len() >= commands::MIN_PATH_QUERY_LEN;
+    let mut results = Vec::new();
+
+    if matches!(kind, None | Some(SearchKind::Apps)) {
+        let index = build_app_index();
+        results.extend(commands::rank(&mut matcher, query, &index, commands::MAX_RESULTS));
+    }
+    if path_query && matches!(kind, None | Some(SearchKind::Folders)) {
+        let index = indexer::build_folder_index();
+        results.extend(commands::rank(&mut matcher, query, &index, commands::MAX_FOLDER_RESULTS));
+    }
+    if path_query && matches!(kind, None | Some(SearchKind::Images)) {
+        let index = indexer::build_image_index();
+        results.extend(commands::rank(&mut matcher, query, &index, commands::MAX_IMAGE_RESULTS));
+    }
+    if kind == Some(SearchKind::Files) {
+        if let Some(args) = commands::content_search_args(query) {
+            // Bundled next to the executable as a sidecar; fall back to $PATH
+            let rg = std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(|dir| dir.join("rg")))
+                .filter(|p| p.is_file())
+                .unwrap_or_else(|| "rg".into());
+            match std::process::Command::new(rg).args(&args).output() {
+                Ok(output) => {
+                    results.extend(commands::content_results(&String::from_utf8_lossy(&output.stdout)))
+                }
+                Err(e) => eprintln!("cheru: cannot run ripgrep: {}", e),
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|r| seen.insert(r.exec.clone()));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results).unwrap_or_default());
+    } else {
+        // Tab-separated so `cut -f2` yields something to open or launch
+        for r in &results {
+            println!("{}\t{}", r.name, r.exec);
+        }
+    }
+    i32::from(results.is_empty())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let command = match cli::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("cheru: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    match &command {
+        cli::Command::Help => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        cli::Command::Version => {
+            println!("cheru {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        cli::Command::CheckConfig => std::process::exit(config::check()),
+        cli::Command::Search { query, kind, json } => {
+            std::process::exit(search_headless(query, *kind, *json))
+        }
+        _ => {}
+    }
+
     tauri::Builder::default()
+        // Must come first: a second `cheru` forwards its arguments here and exits
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            match cli::parse(argv.into_iter().skip(1)) {
+                Ok(command) => handle_command(app, command),
+                Err(e) => eprintln!("Warning: ignoring forwarded command: {}", e),
+            }
+        }))
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_shell::init())
-        .setup(|app| {
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .setup(move |app| {
+            // No other instance is running, so there is nothing to quit
+            if command == cli::Command::Quit {
+                std::process::exit(0);
+            }
+
             // Build app index (fast — no icon conversion yet)
-            let mut index = indexer::build_index();
-            index.extend(indexer::build_system_commands());
+            let index = build_app_index();
             println!("Indexed {} applications", index.len());
 
+            let (cfg, diagnostics) = config::load();
+
             // Store state
             let state = AppState {
                 index: RwLock::new(index),
-                folder_index: OnceLock::new(),
-                image_index: OnceLock::new(),
+                folder_index: RwLock::new(None),
+                image_index: RwLock::new(None),
+                command_index: RwLock::new(None),
+                shell: Mutex::new(Default::default()),
                 matcher: Mutex::new(FuzzyMatcher::new()),
+                calc: Mutex::new(calculator::Context::with_format(calc_format(&cfg))),
+                config: RwLock::new(cfg.clone()),
+                config_diagnostics: RwLock::new(diagnostics),
+                scripts: Mutex::new(Default::default()),
+                #[cfg(target_os = "linux")]
+                mime_db: OnceLock::new(),
+                #[cfg(target_os = "linux")]
+                open_with: Default::default(),
             };
             app.manage(state);
 
+            // Script icons live next to the scripts, under XDG_CONFIG_HOME
+            let _ = app
+                .asset_protocol_scope()
+                .allow_directory(scripts::scripts_dir(), true);
+
             // Spawn background icon conversion (macOS only)
             #[cfg(target_os = "macos")]
             {
+                // The icon cache follows XDG_CACHE_HOME, which may be outside
+                // the asset scope in tauri.conf.json
+                let _ = app
+                    .asset_protocol_scope()
+                    .allow_directory(paths::cache_dir().join("icons"), true);
                 let app_handle = app.handle().clone();
                 std::thread::spawn(move || {
                     let state = app_handle.state::<AppState>();
@@ -97,9 +570,17 @@ pub fn run() {
             }
 
             // Set up system tray
+            let shortcut_status =
+                MenuItemBuilder::with_id("shortcuts", ShortcutBackend::Unavailable.label())
+                    .enabled(false)
+                    .build(app)?;
             let show = MenuItemBuilder::with_id("show", "Show Launcher").build(app)?;
             let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
-            let menu = MenuBuilder::new(app).items(&[&show, &quit]).build()?;
+            let menu = MenuBuilder::new(app)
+                .item(&shortcut_status)
+                .separator()
+                .items(&[&show, &quit])
+                .build()?;
 
             let _tray = TrayIconBuilder::new()
                 .menu(&menu)
@@ -118,24 +599,31 @@ pub fn run() {
                 .build(app)?;
 
             // Register global shortcut from config
-            let cfg = config::load();
             println!("Hotkey: {}", cfg.hotkey);
             // Set up autostart on login
             setup_autostart(cfg.autostart);
-            use tauri_plugin_global_shortcut::GlobalShortcutExt;
-            app.global_shortcut().on_shortcut(cfg.hotkey.as_str(), |app, _shortcut, event| {
-                if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-                    if let Some(window) = app.get_webview_window("launcher") {
-                        if window.is_visible().unwrap_or(false) {
-                            let _ = window.hide();
-                        } else {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.center();
-                        }
-                    }
+            app.manage(Shortcuts {
+                status: shortcut_status,
+                #[cfg(target_os = "linux")]
+                portal: Default::default(),
+                bound: Mutex::new(Vec::new()),
+            });
+            bind_hotkeys(app.handle(), &cfg.hotkeys);
+
+            // Pick up config edits without a restart
+            let app_handle = app.handle().clone();
+            if let Err(e) = config::watch(move || reload_config(&app_handle)) {
+                eprintln!("Warning: cannot watch config for changes: {}", e);
+            }
+
+            // Scripting interface, see control.rs
+            #[cfg(unix)]
+            {
+                let app_handle = app.handle().clone();
+                if let Err(e) = control::serve(move |request| handle_control(&app_handle, request)) {
+                    eprintln!("Warning: cannot open control socket: {}", e);
                 }
-            })?;
+            }
 
             // macOS: hide from Dock
             #[cfg(target_os = "macos")]
@@ -152,6 +640,12 @@ pub fn run() {
                 }
             }
 
+            // Apply the command Cheru was started with, e.g. `cheru show`.
+            // Nothing to reindex or show on a plain start.
+            if !matches!(command, cli::Command::Run | cli::Command::Reindex) {
+                handle_command(app.handle(), command);
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -161,10 +655,36 @@ pub fn run() {
             commands::get_index_size,
             commands::search_folders,
             commands::search_images,
+            commands::search_commands,
+            commands::run_command,
+            commands::run_shell,
+            commands::search_scripts,
+            commands::get_script,
+            commands::run_script,
+            commands::search_quicklinks,
+            commands::web_fallback,
+            commands::resolve_bang,
             commands::open_path,
+            commands::get_actions,
+            commands::execute_action,
+            commands::get_open_with_apps,
+            commands::trash_path,
+            commands::rename_path,
+            commands::duplicate_path,
+            commands::move_path,
+            commands::copy_path,
             commands::browse_directory,
             commands::get_theme,
+            commands::get_config_diagnostics,
+            commands::get_script_diagnostics,
+            commands::get_config,
+            commands::set_config_value,
+            commands::get_setting_choices,
             commands::eval_expression,
+            commands::eval_datetime,
+            commands::commit_calculation,
+            commands::get_calc_history,
+            commands::copy_to_clipboard,
             commands::run_system_command,
             commands::open_url,
             commands::search_file_contents,
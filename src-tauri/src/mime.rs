@@ -0,0 +1,460 @@
+//! MIME type detection from the shared-mime-info database: `globs2` for file
+//! names, `magic` for contents and `subclasses` for parent types.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use crate::paths;
+
+/// Never read more than this much of a file for magic matching.
+const MAX_MAGIC_EXTENT: usize = 64 * 1024;
+
+struct Glob {
+    weight: u32,
+    mime: String,
+    pattern: String,
+    case_sensitive: bool,
+}
+
+struct MagicMatch {
+    indent: u32,
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    /// Number of offsets to try, starting at `offset`
+    range: usize,
+}
+
+struct MagicRule {
+    priority: u32,
+    mime: String,
+    matches: Vec<MagicMatch>,
+}
+
+#[derive(Default)]
+pub struct MimeDb {
+    globs: Vec<Glob>,
+    magic: Vec<MagicRule>,
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl MimeDb {
+    /// Read the database from every XDG data directory, the user's first.
+    pub fn load() -> Self {
+        let mut db = MimeDb::default();
+        for dir in paths::data_dirs() {
+            let dir = dir.join("mime");
+            if let Ok(contents) = std::fs::read_to_string(dir.join("globs2")) {
+                db.add_globs(&contents);
+            }
+            if let Ok(contents) = std::fs::read(dir.join("magic")) {
+                db.add_magic(&contents);
+            }
+            if let Ok(contents) = std::fs::read_to_string(dir.join("subclasses")) {
+                db.add_subclasses(&contents);
+            }
+        }
+        db.magic
+            .sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        db
+    }
+
+    /// Lines of `weight:type:pattern[:flags]`.
+    fn add_globs(&mut self, contents: &str) {
+        for line in contents.lines().filter(|l| !l.starts_with('#')) {
+            let mut fields = line.splitn(4, ':');
+            let (Some(weight), Some(mime), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(weight) = weight.parse() else {
+                continue;
+            };
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|f| f.split(',').any(|f| f == "cs"));
+            self.globs.push(Glob {
+                weight,
+                mime: mime.to_string(),
+                pattern: if case_sensitive {
+                    pattern.to_string()
+                } else {
+                    pattern.to_lowercase()
+                },
+                case_sensitive,
+            });
+        }
+    }
+
+    /// Lines of `child parent`.
+    fn add_subclasses(&mut self, contents: &str) {
+        for line in contents.lines() {
+            if let Some((child, parent)) = line.split_once(' ') {
+                self.parents
+                    .entry(child.to_string())
+                    .or_default()
+                    .push(parent.to_string());
+            }
+        }
+    }
+
+    /// The binary `magic` file: a `MIME-Magic\0\n` header, then sections of
+    /// `[priority:type]\n` followed by match lines.
+    fn add_magic(&mut self, contents: &[u8]) {
+        let Some(mut rest) = contents.strip_prefix(b"MIME-Magic\0\n") else {
+            return;
+        };
+        while let Some(body) = rest.strip_prefix(b"[") {
+            let Some(end) = body.iter().position(|&b| b == b'\n') else {
+                return;
+            };
+            let header = String::from_utf8_lossy(&body[..end]);
+            let Some((priority, mime)) = header.trim_end_matches(']').split_once(':') else {
+                return;
+            };
+            let mut rule = MagicRule {
+                priority: priority.parse().unwrap_or(50),
+                mime: mime.to_string(),
+                matches: Vec::new(),
+            };
+            rest = &body[end + 1..];
+            while !rest.is_empty() && rest[0] != b'[' {
+                match parse_magic_line(rest) {
+                    Some((m, next)) => {
+                        rule.matches.push(m);
+                        rest = next;
+                    }
+                    // A format we don't know; skip to the next section
+                    None => {
+                        let skip = rest.windows(2).position(|w| w == b"\n[");
+                        rest = skip.map_or(&[][..], |i| &rest[i + 1..]);
+                    }
+                }
+            }
+            self.magic.push(rule);
+        }
+    }
+
+    /// Best guess at the MIME type of `path`.
+    pub fn guess(&self, path: &Path) -> String {
+        if path.is_dir() {
+            return "inode/directory".to_string();
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let candidates = self.guess_from_name(&name);
+        if let [mime] = candidates.as_slice() {
+            return mime.to_string();
+        }
+
+        let mut head = Vec::new();
+        if let Ok(file) = std::fs::File::open(path) {
+            let _ = file.take(self.magic_extent() as u64).read_to_end(&mut head);
+        }
+        let magic = self.guess_from_contents(&head);
+        // Magic settles conflicting globs; failing that, the first one wins
+        if let Some(first) = candidates.first() {
+            let settled = magic.filter(|m| candidates.contains(m));
+            return settled.unwrap_or(first).to_string();
+        }
+        if let Some(mime) = magic {
+            return mime.to_string();
+        }
+        if !head.is_empty() && !head.iter().take(1024).any(|&b| b == 0) {
+            return "text/plain".to_string();
+        }
+        // Lets a remembered choice still tell unknown types apart
+        match path.extension() {
+            Some(ext) => format!(
+                "application/x-extension-{}",
+                ext.to_string_lossy().to_lowercase()
+            ),
+            None => "application/octet-stream".to_string(),
+        }
+    }
+
+    /// Types of the heaviest matching globs, preferring case-sensitive and
+    /// then longer patterns. More than one means magic has to decide.
+    fn guess_from_name(&self, name: &str) -> Vec<&str> {
+        let lower = name.to_lowercase();
+        let mut best = Vec::new();
+        let mut best_rank = None;
+        for glob in &self.globs {
+            let subject = if glob.case_sensitive { name } else { &lower };
+            if !glob_match(glob.pattern.as_bytes(), subject.as_bytes()) {
+                continue;
+            }
+            let rank = Some((glob.weight, glob.case_sensitive, glob.pattern.len()));
+            if rank > best_rank {
+                best.clear();
+                best_rank = rank;
+            }
+            if rank == best_rank && !best.contains(&glob.mime.as_str()) {
+                best.push(glob.mime.as_str());
+            }
+        }
+        best
+    }
+
+    fn guess_from_contents(&self, head: &[u8]) -> Option<&str> {
+        if head.is_empty() {
+            return None;
+        }
+        self.magic
+            .iter()
+            .find(|rule| matches_at_level(&rule.matches, 0, head))
+            .map(|rule| rule.mime.as_str())
+    }
+
+    fn magic_extent(&self) -> usize {
+        self.magic
+            .iter()
+            .flat_map(|rule| &rule.matches)
+            .map(|m| m.offset + m.range + m.value.len())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_MAGIC_EXTENT)
+    }
+
+    /// `mime` followed by the types it is a subclass of, nearest first.
+    pub fn with_parents(&self, mime: &str) -> Vec<String> {
+        let mut types = vec![mime.to_string()];
+        let mut i = 0;
+        while i < types.len() {
+            let mut parents = self.parents.get(&types[i]).cloned().unwrap_or_default();
+            // Implied by the spec rather than listed
+            if types[i].starts_with("text/") {
+                parents.push("text/plain".to_string());
+            }
+            for parent in parents {
+                if !types.contains(&parent) {
+                    types.push(parent);
+                }
+            }
+            i += 1;
+        }
+        types
+    }
+}
+
+/// One `[indent]>offset=<len:u16 BE><value>[&mask][~word][+range]\n` line.
+fn parse_magic_line(line: &[u8]) -> Option<(MagicMatch, &[u8])> {
+    let (indent, rest) = match line.iter().position(|&b| b == b'>')? {
+        0 => (0, &line[1..]),
+        n => (
+            std::str::from_utf8(&line[..n]).ok()?.parse().ok()?,
+            &line[n + 1..],
+        ),
+    };
+    let eq = rest.iter().position(|&b| b == b'=')?;
+    let offset = std::str::from_utf8(&rest[..eq]).ok()?.parse().ok()?;
+    let rest = &rest[eq + 1..];
+    let len = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+    let mut value = rest.get(2..2 + len)?.to_vec();
+    let mut rest = &rest[2 + len..];
+
+    let mut mask = None;
+    if let Some(after) = rest.strip_prefix(b"&") {
+        mask = Some(after.get(..len)?.to_vec());
+        rest = &after[len..];
+    }
+    let mut word_size = 1;
+    let mut range = 1;
+    while let Some((&flag, after)) = rest.split_first().filter(|(&f, _)| f != b'\n') {
+        let digits = after.iter().take_while(|b| b.is_ascii_digit()).count();
+        let number: usize = std::str::from_utf8(&after[..digits]).ok()?.parse().ok()?;
+        match flag {
+            b'~' => word_size = number,
+            b'+' => range = number,
+            _ => return None,
+        }
+        rest = &after[digits..];
+    }
+    let rest = rest.strip_prefix(b"\n")?;
+
+    // Values are big-endian words; the host reads them the other way round
+    if cfg!(target_endian = "little") && matches!(word_size, 2 | 4) {
+        value.chunks_mut(word_size).for_each(|w| w.reverse());
+        if let Some(mask) = mask.as_mut() {
+            mask.chunks_mut(word_size).for_each(|w| w.reverse());
+        }
+    }
+
+    Some((
+        MagicMatch {
+            indent,
+            offset,
+            value,
+            mask,
+            range: range.max(1),
+        },
+        rest,
+    ))
+}
+
+/// A rule matches if one of its matches at `level` does, along with one of
+/// the nested matches that follow it, if any.
+fn matches_at_level(matches: &[MagicMatch], level: u32, data: &[u8]) -> bool {
+    let mut i = 0;
+    while i < matches.len() {
+        let end = matches[i + 1..]
+            .iter()
+            .position(|m| m.indent <= level)
+            .map_or(matches.len(), |p| i + 1 + p);
+        if matches[i].indent == level && magic_match(&matches[i], data) {
+            let children = &matches[i + 1..end];
+            if children.is_empty() || matches_at_level(children, level + 1, data) {
+                return true;
+            }
+        }
+        i = end;
+    }
+    false
+}
+
+fn magic_match(m: &MagicMatch, data: &[u8]) -> bool {
+    (m.offset..m.offset + m.range).any(|start| {
+        let Some(window) = data.get(start..start + m.value.len()) else {
+            return false;
+        };
+        match &m.mask {
+            Some(mask) => window
+                .iter()
+                .zip(&m.value)
+                .zip(mask)
+                .all(|((d, v), k)| d & k == v & k),
+            None => window == m.value.as_slice(),
+        }
+    })
+}
+
+/// fnmatch-style matching with `*`, `?` and `[...]`.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_match(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+        Some((b'[', rest)) => {
+            let Some(close) = rest.iter().skip(1).position(|&b| b == b']').map(|p| p + 1) else {
+                return name.first() == Some(&b'[') && glob_match(rest, &name[1..]);
+            };
+            let Some(&c) = name.first() else {
+                return false;
+            };
+            let (negate, class) = match rest[..close].split_first() {
+                Some((b'!' | b'^', class)) => (true, class),
+                _ => (false, &rest[..close]),
+            };
+            let mut found = false;
+            let mut j = 0;
+            while j < class.len() {
+                if j + 2 < class.len() && class[j + 1] == b'-' {
+                    found |= (class[j]..=class[j + 2]).contains(&c);
+                    j += 3;
+                } else {
+                    found |= class[j] == c;
+                    j += 1;
+                }
+            }
+            found != negate && glob_match(&rest[close + 1..], &name[1..])
+        }
+        Some((&p, rest)) => name.first() == Some(&p) && glob_match(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> MimeDb {
+        let mut db = MimeDb::default();
+        db.add_globs(
+            "# comment\n\
+             50:image/png:*.png\n\
+             50:application/x-compressed-tar:*.tar.gz\n\
+             50:application/gzip:*.gz\n\
+             50:text/x-makefile:makefile\n\
+             50:text/x-c++src:*.C:cs\n\
+             50:text/x-csrc:*.c\n\
+             50:text/x-readme:README*\n\
+             50:application/json:*.json\n\
+             50:application/schema+json:*.json\n",
+        );
+        db.add_subclasses(
+            "text/x-csrc text/plain\napplication/x-compressed-tar application/gzip\n",
+        );
+        let mut magic = b"MIME-Magic\0\n[80:application/pdf]\n>0=\0\x05%PDF-\n".to_vec();
+        magic.extend(b"[50:image/png]\n>0=\0\x04\x89PNG\n");
+        magic.extend(b"[40:application/x-test]\n>0=\0\x02AB\n1>4=\0\x01C+2\n");
+        db.add_magic(&magic);
+        db.magic
+            .sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        db
+    }
+
+    #[test]
+    fn test_guess_from_name() {
+        let db = db();
+        assert_eq!(db.guess_from_name("Photo.PNG"), ["image/png"]);
+        assert_eq!(
+            db.guess_from_name("src.tar.gz"),
+            ["application/x-compressed-tar"]
+        );
+        assert_eq!(db.guess_from_name("notes.gz"), ["application/gzip"]);
+        assert_eq!(db.guess_from_name("Makefile"), ["text/x-makefile"]);
+        assert_eq!(db.guess_from_name("main.C"), ["text/x-c++src"]);
+        assert_eq!(db.guess_from_name("main.c"), ["text/x-csrc"]);
+        assert_eq!(db.guess_from_name("README.md"), ["text/x-readme"]);
+        assert_eq!(
+            db.guess_from_name("schema.json"),
+            ["application/json", "application/schema+json"]
+        );
+        assert!(db.guess_from_name("data.bin").is_empty());
+    }
+
+    #[test]
+    fn test_guess_from_contents() {
+        let db = db();
+        assert_eq!(
+            db.guess_from_contents(b"%PDF-1.7\n..."),
+            Some("application/pdf")
+        );
+        assert_eq!(db.guess_from_contents(b"\x89PNG\r\n"), Some("image/png"));
+        assert_eq!(
+            db.guess_from_contents(b"AB..xC"),
+            Some("application/x-test")
+        );
+        assert_eq!(db.guess_from_contents(b"AB....C"), None);
+        assert_eq!(db.guess_from_contents(b"plain"), None);
+        assert_eq!(db.magic_extent(), 7);
+    }
+
+    #[test]
+    fn test_parents() {
+        let db = db();
+        assert_eq!(
+            db.with_parents("text/x-csrc"),
+            vec!["text/x-csrc", "text/plain"]
+        );
+        assert_eq!(
+            db.with_parents("text/x-readme"),
+            vec!["text/x-readme", "text/plain"]
+        );
+        assert_eq!(
+            db.with_parents("application/x-compressed-tar"),
+            vec!["application/x-compressed-tar", "application/gzip"]
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*.[ch]", b"x.h"));
+        assert!(!glob_match(b"*.[!ch]", b"x.h"));
+        assert!(glob_match(b"file?.txt", b"file1.txt"));
+        assert!(glob_match(b"[0-9]*", b"7zip"));
+    }
+}
@@ -0,0 +1,402 @@
+//! Open With on Linux: the apps `mimeapps.list` and desktop entries associate
+//! with a MIME type, the choice last made per type, and expanding a desktop
+//! entry's `Exec` line for a file.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::actions;
+use crate::paths;
+
+/// One `mimeapps.list` file.
+#[derive(Debug, Default, PartialEq)]
+pub struct MimeApps {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+/// An app that can open the file, by desktop file ID.
+#[derive(Debug, PartialEq)]
+pub struct Handler {
+    pub id: String,
+    /// Why it is listed first, when it is
+    pub note: Option<&'static str>,
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Defaults,
+    Added,
+    Removed,
+}
+
+pub fn parse_mimeapps(contents: &str) -> MimeApps {
+    let mut apps = MimeApps::default();
+    let mut section = None;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            section = match line {
+                "[Default Applications]" => Some(Section::Defaults),
+                "[Added Associations]" => Some(Section::Added),
+                "[Removed Associations]" => Some(Section::Removed),
+                _ => None,
+            };
+            continue;
+        }
+        let (Some(section), Some((mime, ids))) = (section, line.split_once('=')) else {
+            continue;
+        };
+        let map = match section {
+            Section::Defaults => &mut apps.defaults,
+            Section::Added => &mut apps.added,
+            Section::Removed => &mut apps.removed,
+        };
+        map.entry(mime.trim().to_string()).or_default().extend(
+            ids.split(';')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string),
+        );
+    }
+    apps
+}
+
+/// Every `mimeapps.list`, most important first.
+pub fn load_mimeapps() -> Vec<MimeApps> {
+    mimeapps_files()
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|contents| parse_mimeapps(&contents))
+        .collect()
+}
+
+/// `$desktop-mimeapps.list` before `mimeapps.list`, in the config
+/// directories and then the data directories' `applications/`.
+fn mimeapps_files() -> Vec<PathBuf> {
+    let desktops: Vec<String> = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let dirs = paths::config_dirs().into_iter().chain(
+        paths::data_dirs()
+            .into_iter()
+            .map(|d| d.join("applications")),
+    );
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        for desktop in &desktops {
+            files.push(dir.join(format!("{}-mimeapps.list", desktop)));
+        }
+        files.push(dir.join("mimeapps.list"));
+    }
+    files
+}
+
+/// Order the installed apps that can open a file of `types` (most specific
+/// first): the remembered choice, then each type's defaults, added
+/// associations and `MimeType=` declarations. Removed associations hide
+/// those from less important lists and from declarations.
+pub fn rank_handlers(
+    types: &[String],
+    remembered: Option<&str>,
+    lists: &[MimeApps],
+    installed: &[(&str, &[String])],
+) -> Vec<Handler> {
+    let ids: HashSet<&str> = installed.iter().map(|(id, _)| *id).collect();
+    let mut handlers: Vec<Handler> = Vec::new();
+    let mut push = |id: &str, note: Option<&'static str>| {
+        if ids.contains(id) && !handlers.iter().any(|h| h.id == id) {
+            handlers.push(Handler {
+                id: id.to_string(),
+                note,
+            });
+        }
+    };
+
+    if let Some(id) = remembered {
+        push(id, Some("Last used"));
+    }
+    for mime in types {
+        for id in lists.iter().filter_map(|l| l.defaults.get(mime)).flatten() {
+            push(id, Some("Default"));
+        }
+        let mut removed = HashSet::new();
+        for list in lists {
+            for id in list.added.get(mime).into_iter().flatten() {
+                if !removed.contains(id) {
+                    push(id, None);
+                }
+            }
+            removed.extend(list.removed.get(mime).into_iter().flatten());
+        }
+        for (id, declared) in installed {
+            if declared.contains(mime) && !removed.contains(&id.to_string()) {
+                push(id, None);
+            }
+        }
+    }
+    handlers
+}
+
+/// The app last chosen for files of type `mime`.
+pub fn remembered(mime: &str) -> Option<String> {
+    load_from(&choices_path()).remove(mime)
+}
+
+/// Offer `id` first for files of type `mime` from now on.
+pub fn remember(mime: &str, id: &str) -> io::Result<()> {
+    remember_in(&choices_path(), mime, id)
+}
+
+fn load_from(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn remember_in(path: &Path, mime: &str, id: &str) -> io::Result<()> {
+    let mut choices = load_from(path);
+    choices.insert(mime.to_string(), id.to_string());
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&choices).map_err(io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+fn choices_path() -> PathBuf {
+    paths::data_dir().join("open_with.json")
+}
+
+/// Expand a desktop entry's `Exec` line to open `file`: `%f`/`%F` become
+/// the path, `%u`/`%U` its URI, and the file is appended when the line
+/// takes none.
+pub fn exec_args(
+    exec: &str,
+    file: &Path,
+    name: &str,
+    icon: Option<&str>,
+    desktop_file: &Path,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut took_file = false;
+    for word in split_exec(exec) {
+        if word == "%i" {
+            if let Some(icon) = icon {
+                args.extend(["--icon".to_string(), icon.to_string()]);
+            }
+            continue;
+        }
+        let mut arg = String::new();
+        let mut chars = word.chars();
+        let mut dropped = false;
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                arg.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('f' | 'F') => {
+                    arg.push_str(&file.to_string_lossy());
+                    took_file = true;
+                }
+                Some('u' | 'U') => {
+                    arg.push_str(&actions::file_uri(file));
+                    took_file = true;
+                }
+                Some('c') => arg.push_str(name),
+                Some('k') => arg.push_str(&desktop_file.to_string_lossy()),
+                Some('%') => arg.push('%'),
+                // Deprecated and unknown codes expand to nothing
+                _ => dropped = true,
+            }
+        }
+        if !(dropped && arg.is_empty()) {
+            args.push(arg);
+        }
+    }
+    if !took_file {
+        args.push(file.to_string_lossy().to_string());
+    }
+    args
+}
+
+/// Split an `Exec` line into words, honoring double quotes and backslash
+/// escapes.
+fn split_exec(exec: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIMEAPPS: &str = "\
+[Default Applications]
+text/plain=org.gnome.TextEditor.desktop;
+
+[Added Associations]
+text/plain=code.desktop;vim.desktop;
+image/png=gimp.desktop;
+
+[Removed Associations]
+text/plain=libreoffice-writer.desktop;
+";
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_mimeapps() {
+        let apps = parse_mimeapps(MIMEAPPS);
+        assert_eq!(
+            apps.defaults["text/plain"],
+            strings(&["org.gnome.TextEditor.desktop"])
+        );
+        assert_eq!(
+            apps.added["text/plain"],
+            strings(&["code.desktop", "vim.desktop"])
+        );
+        assert_eq!(
+            apps.removed["text/plain"],
+            strings(&["libreoffice-writer.desktop"])
+        );
+        assert_eq!(
+            parse_mimeapps("# nothing\n[Other]\na=b;\n"),
+            MimeApps::default()
+        );
+    }
+
+    #[test]
+    fn test_rank_handlers() {
+        let text = strings(&["text/plain"]);
+        let none: Vec<String> = Vec::new();
+        let installed: Vec<(&str, &[String])> = vec![
+            ("libreoffice-writer.desktop", &text),
+            ("gedit.desktop", &text),
+            ("vim.desktop", &none),
+            ("org.gnome.TextEditor.desktop", &text),
+            ("gimp.desktop", &none),
+        ];
+        let lists = [parse_mimeapps(MIMEAPPS)];
+        let ids = |remembered| -> Vec<String> {
+            rank_handlers(
+                &strings(&["text/markdown", "text/plain"]),
+                remembered,
+                &lists,
+                &installed,
+            )
+            .into_iter()
+            .map(|h| h.id)
+            .collect()
+        };
+
+        // code.desktop isn't installed; Writer's association was removed
+        assert_eq!(
+            ids(None),
+            strings(&[
+                "org.gnome.TextEditor.desktop",
+                "vim.desktop",
+                "gedit.desktop"
+            ])
+        );
+        assert_eq!(
+            ids(Some("gedit.desktop")),
+            strings(&[
+                "gedit.desktop",
+                "org.gnome.TextEditor.desktop",
+                "vim.desktop"
+            ])
+        );
+        let first = &rank_handlers(&text, None, &lists, &installed)[0];
+        assert_eq!(first.note, Some("Default"));
+    }
+
+    #[test]
+    fn test_remember_choice() {
+        let dir = std::env::temp_dir().join(format!("cheru-test-open-with-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("open_with.json");
+
+        remember_in(&path, "image/png", "gimp.desktop").unwrap();
+        remember_in(&path, "image/png", "org.gnome.Loupe.desktop").unwrap();
+        remember_in(&path, "text/plain", "vim.desktop").unwrap();
+        let choices = load_from(&path);
+        assert_eq!(choices["image/png"], "org.gnome.Loupe.desktop");
+        assert_eq!(choices["text/plain"], "vim.desktop");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_exec_args() {
+        let file = Path::new("/home/me/My Notes.md");
+        let desktop = Path::new("/usr/share/applications/app.desktop");
+        let args = |exec| exec_args(exec, file, "App", Some("app-icon"), desktop);
+
+        assert_eq!(
+            args("gedit %U"),
+            strings(&["gedit", "file:///home/me/My%20Notes.md"])
+        );
+        assert_eq!(args("vim %f"), strings(&["vim", "/home/me/My Notes.md"]));
+        assert_eq!(
+            args("app %i --title=%c %k"),
+            strings(&[
+                "app",
+                "--icon",
+                "app-icon",
+                "--title=App",
+                "/usr/share/applications/app.desktop",
+                "/home/me/My Notes.md"
+            ])
+        );
+        assert_eq!(
+            args("sh -c \"echo \\\"100%%\\\"\" %d %F"),
+            strings(&["sh", "-c", "echo \"100%\"", "/home/me/My Notes.md"])
+        );
+        assert_eq!(args("okular"), strings(&["okular", "/home/me/My Notes.md"]));
+    }
+}
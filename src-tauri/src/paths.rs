@@ -0,0 +1,169 @@
+//! Where Cheru keeps its files, following the XDG base directory spec.
+//!
+//! `CHERU_CONFIG` points at an alternative config.toml; the directory that
+//! contains it is then used for everything else Cheru reads from its config
+//! directory.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "cheru";
+
+/// The config file, `$XDG_CONFIG_HOME/cheru/config.toml` unless overridden
+/// by `CHERU_CONFIG`.
+pub fn config_file() -> PathBuf {
+    match std::env::var_os("CHERU_CONFIG").filter(|v| !v.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => config_dir().join("config.toml"),
+    }
+}
+
+/// Directory holding config.toml and other user-editable files.
+pub fn config_dir() -> PathBuf {
+    match std::env::var_os("CHERU_CONFIG").filter(|v| !v.is_empty()) {
+        Some(path) => PathBuf::from(path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        None => config_home().join(APP_DIR),
+    }
+}
+
+/// Regenerable files such as converted icons.
+pub fn cache_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_CACHE_HOME"), &home(), ".cache").join(APP_DIR)
+}
+
+/// Persistent state such as calculator history.
+pub fn data_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_DATA_HOME"), &home(), ".local/share").join(APP_DIR)
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, most important first.
+/// Shared databases such as `mime/` and `applications/` live here.
+#[cfg(target_os = "linux")]
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![base_dir(
+        std::env::var_os("XDG_DATA_HOME"),
+        &home(),
+        ".local/share",
+    )];
+    dirs.extend(search_dirs(
+        std::env::var_os("XDG_DATA_DIRS"),
+        "/usr/local/share:/usr/share",
+    ));
+    dirs
+}
+
+/// `$XDG_CONFIG_HOME` followed by `$XDG_CONFIG_DIRS`, most important first.
+#[cfg(target_os = "linux")]
+pub fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![config_home()];
+    dirs.extend(search_dirs(std::env::var_os("XDG_CONFIG_DIRS"), "/etc/xdg"));
+    dirs
+}
+
+/// The home trash, `$XDG_DATA_HOME/Trash`.
+#[cfg(target_os = "linux")]
+pub fn trash_dir() -> PathBuf {
+    base_dir(std::env::var_os("XDG_DATA_HOME"), &home(), ".local/share").join("Trash")
+}
+
+/// The control socket, in a folder of its own that `control::serve` keeps
+/// private: under `$XDG_RUNTIME_DIR` when the session provides one, and
+/// otherwise in the cache folder.
+#[cfg(unix)]
+pub fn socket_file() -> PathBuf {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+    {
+        Some(dir) => dir.join(APP_DIR),
+        None => cache_dir().join("run"),
+    };
+    dir.join("cheru.sock")
+}
+
+/// `$XDG_CONFIG_HOME/autostart`, shared with other applications.
+#[cfg(target_os = "linux")]
+pub fn autostart_dir() -> PathBuf {
+    config_home().join("autostart")
+}
+
+fn config_home() -> PathBuf {
+    base_dir(std::env::var_os("XDG_CONFIG_HOME"), &home(), ".config")
+}
+
+fn home() -> PathBuf {
+    dirs::home_dir().unwrap_or_default()
+}
+
+/// Resolve an XDG base directory. The spec says relative values are
+/// invalid and must be ignored, as are empty ones.
+fn base_dir(value: Option<OsString>, home: &Path, fallback: &str) -> PathBuf {
+    value
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home.join(fallback))
+}
+
+/// Resolve a colon-separated XDG search path, skipping relative entries.
+#[cfg(any(target_os = "linux", test))]
+fn search_dirs(value: Option<OsString>, fallback: &str) -> Vec<PathBuf> {
+    let dirs: Vec<PathBuf> = value
+        .iter()
+        .flat_map(std::env::split_paths)
+        .filter(|p| p.is_absolute())
+        .collect();
+    if dirs.is_empty() {
+        std::env::split_paths(fallback).collect()
+    } else {
+        dirs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_dir_prefers_absolute_variable() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            base_dir(Some("/tmp/xdg".into()), home, ".cache"),
+            PathBuf::from("/tmp/xdg")
+        );
+        assert_eq!(
+            base_dir(None, home, ".cache"),
+            PathBuf::from("/home/me/.cache")
+        );
+    }
+
+    #[test]
+    fn test_base_dir_ignores_relative_and_empty() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            base_dir(Some("relative/dir".into()), home, ".local/share"),
+            PathBuf::from("/home/me/.local/share")
+        );
+        assert_eq!(
+            base_dir(Some("".into()), home, ".config"),
+            PathBuf::from("/home/me/.config")
+        );
+    }
+
+    #[test]
+    fn test_search_dirs() {
+        assert_eq!(
+            search_dirs(Some("/opt/share:rel:/usr/share".into()), "/fallback"),
+            vec![PathBuf::from("/opt/share"), PathBuf::from("/usr/share")]
+        );
+        assert_eq!(
+            search_dirs(Some("".into()), "/usr/local/share:/usr/share"),
+            vec![
+                PathBuf::from("/usr/local/share"),
+                PathBuf::from("/usr/share")
+            ]
+        );
+    }
+}
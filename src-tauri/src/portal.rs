@@ -0,0 +1,157 @@
+//! Global shortcuts through `org.freedesktop.portal.GlobalShortcuts`. Wayland
+//! compositors don't allow the X11 grabs the global-shortcut plugin relies on,
+//! so bindings are requested from the desktop instead. The user may confirm or
+//! change them in the system settings; the configured keys are only a hint.
+
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use ashpd::desktop::Session as PortalSession;
+use futures_util::{Stream, StreamExt};
+
+use crate::config::Hotkey;
+
+/// True when running in a Wayland session, where X11 grabs never fire.
+pub fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some_and(|v| !v.is_empty())
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|v| v == "wayland")
+}
+
+/// An open portal session with Cheru's shortcuts bound.
+pub struct Session {
+    proxy: GlobalShortcuts<'static>,
+    session: PortalSession<'static, GlobalShortcuts<'static>>,
+}
+
+/// Bind `hotkeys`, identified by their mode. Waits for the user to confirm
+/// if the desktop asks.
+pub async fn bind(hotkeys: &[Hotkey]) -> Result<Session, ashpd::Error> {
+    let proxy = GlobalShortcuts::new().await?;
+    let session = proxy.create_session().await?;
+    let shortcuts: Vec<NewShortcut> = hotkeys
+        .iter()
+        .map(|hotkey| {
+            NewShortcut::new(hotkey.mode.as_str(), description(&hotkey.mode))
+                .preferred_trigger(trigger(&hotkey.key).as_deref())
+        })
+        .collect();
+    proxy
+        .bind_shortcuts(&session, &shortcuts, None)
+        .await?
+        .response()?;
+    Ok(Session { proxy, session })
+}
+
+impl Session {
+    /// Modes of the shortcuts as they are pressed.
+    pub async fn activations(&self) -> Result<impl Stream<Item = String>, ashpd::Error> {
+        let activated = self.proxy.receive_activated().await?;
+        Ok(activated.map(|event| event.shortcut_id().to_string()))
+    }
+
+    pub async fn close(self) {
+        let _ = self.session.close().await;
+    }
+}
+
+/// Shown next to the shortcut in the desktop's settings.
+fn description(mode: &str) -> String {
+    match mode {
+        "toggle" => "Show or hide Cheru".to_string(),
+        "search" => "Open Cheru".to_string(),
+        "files" => "Search file contents".to_string(),
+        "calc" => "Show calculator history".to_string(),
+        other => format!("Cheru: {}", other),
+    }
+}
+
+/// Convert an accelerator like `Alt+Space` to the portal's trigger syntax,
+/// `ALT+space`. `None` for keys without an XKB name; the user then picks one.
+fn trigger(accelerator: &str) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut tokens = accelerator.split('+').map(str::trim).peekable();
+    while let Some(token) = tokens.next() {
+        if tokens.peek().is_some() {
+            let modifier = match token.to_ascii_lowercase().as_str() {
+                "alt" | "option" => "ALT",
+                "shift" => "SHIFT",
+                "ctrl" | "control" | "commandorcontrol" | "commandorctrl" | "cmdorctrl"
+                | "cmdorcontrol" => "CTRL",
+                "super" | "meta" | "cmd" | "command" | "logo" => "LOGO",
+                _ => return None,
+            };
+            parts.push(modifier.to_string());
+        } else {
+            parts.push(keysym(token)?);
+        }
+    }
+    Some(parts.join("+"))
+}
+
+/// XKB keysym name for a key in accelerator syntax.
+fn keysym(key: &str) -> Option<String> {
+    let code = key
+        .strip_prefix("Key")
+        .filter(|k| k.len() == 1)
+        .unwrap_or(key);
+    let code = code
+        .strip_prefix("Digit")
+        .filter(|k| k.len() == 1)
+        .unwrap_or(code);
+    if code.len() == 1 && code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Some(code.to_ascii_lowercase());
+    }
+    if code.len() > 1
+        && (code.starts_with('F') || code.starts_with('f'))
+        && code[1..].parse::<u8>().is_ok_and(|n| (1..=24).contains(&n))
+    {
+        return Some(code.to_ascii_uppercase());
+    }
+    let name = match code.to_ascii_lowercase().as_str() {
+        "space" => "space",
+        "enter" | "return" => "Return",
+        "tab" => "Tab",
+        "escape" | "esc" => "Escape",
+        "backspace" => "BackSpace",
+        "delete" => "Delete",
+        "insert" => "Insert",
+        "home" => "Home",
+        "end" => "End",
+        "pageup" => "Page_Up",
+        "pagedown" => "Page_Down",
+        "up" | "arrowup" => "Up",
+        "down" | "arrowdown" => "Down",
+        "left" | "arrowleft" => "Left",
+        "right" | "arrowright" => "Right",
+        "backquote" | "`" => "grave",
+        "minus" | "-" => "minus",
+        "equal" | "=" => "equal",
+        "comma" | "," => "comma",
+        "period" | "." => "period",
+        "slash" | "/" => "slash",
+        "backslash" | "\\" => "backslash",
+        "semicolon" | ";" => "semicolon",
+        "quote" | "'" => "apostrophe",
+        "bracketleft" | "[" => "bracketleft",
+        "bracketright" | "]" => "bracketright",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accelerator_to_trigger() {
+        assert_eq!(trigger("Alt+Space").as_deref(), Some("ALT+space"));
+        assert_eq!(
+            trigger("CommandOrControl+Shift+KeyK").as_deref(),
+            Some("CTRL+SHIFT+k")
+        );
+        assert_eq!(trigger("Super+Digit1").as_deref(), Some("LOGO+1"));
+        assert_eq!(trigger("f12").as_deref(), Some("F12"));
+        assert_eq!(trigger("Ctrl+PageDown").as_deref(), Some("CTRL+Page_Down"));
+        assert_eq!(trigger("Hyper+Space"), None);
+        assert_eq!(trigger("Alt+MediaPlay"), None);
+    }
+}
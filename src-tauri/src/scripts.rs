@@ -0,0 +1,445 @@
+//! Script commands: executables in the `scripts` folder next to config.toml,
+//! described by `@cheru.*` comments the way Raycast script commands are by
+//! `@raycast.*` ones (which are read too).
+//!
+//! ```sh
+//! #!/bin/bash
+//! # @cheru.title Deploy
+//! # @cheru.description Deploy a branch to staging
+//! # @cheru.icon 🚀
+//! # @cheru.argument1 { "placeholder": "branch" }
+//! # @cheru.argument2 { "placeholder": "message", "optional": true }
+//! # @cheru.mode notification
+//! # @cheru.timeout 120
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::{Diagnostic, Severity};
+use crate::paths;
+
+/// Seconds a script may run without `@cheru.timeout`.
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// What happens with a script's output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Discarded
+    #[default]
+    Silent,
+    /// The last line is shown as a desktop notification
+    Notification,
+    /// Shown in the launcher as it arrives
+    Inline,
+    /// Copied to the clipboard once the script ends
+    Clipboard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Argument {
+    pub placeholder: String,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Script {
+    /// File name in the scripts folder
+    pub name: String,
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub title: String,
+    pub description: Option<String>,
+    /// An emoji, or the absolute path of an image
+    pub icon: Option<String>,
+    pub arguments: Vec<Argument>,
+    pub mode: Mode,
+    #[serde(skip)]
+    pub timeout: Duration,
+}
+
+impl Script {
+    /// Check `args` against the declared arguments.
+    pub fn check_arguments(&self, args: &[String]) -> Result<(), String> {
+        if args.len() > self.arguments.len() {
+            return Err(format!(
+                "{} takes {} argument(s)",
+                self.title,
+                self.arguments.len()
+            ));
+        }
+        let required = self
+            .arguments
+            .iter()
+            .rposition(|a| !a.optional)
+            .map_or(0, |i| i + 1);
+        match self.arguments.get(args.len()) {
+            Some(missing) if args.len() < required => {
+                Err(format!("{} needs {}", self.title, missing.placeholder))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `<config dir>/scripts`.
+pub fn scripts_dir() -> PathBuf {
+    paths::config_dir().join("scripts")
+}
+
+/// The scripts folder as last read. Only scripts whose file changed since
+/// are read again, and only their header.
+#[derive(Default)]
+pub struct Cache {
+    files: HashMap<PathBuf, CachedScript>,
+}
+
+struct CachedScript {
+    modified: SystemTime,
+    /// `None` for a file without a title
+    parsed: Result<Option<Script>, Diagnostic>,
+}
+
+impl Cache {
+    /// Catch up with the scripts folder.
+    pub fn refresh(&mut self) {
+        self.refresh_from(&scripts_dir());
+    }
+
+    fn refresh_from(&mut self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            self.files.clear();
+            return;
+        };
+        let mut present = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            if name.starts_with('.') || !is_executable(&path) {
+                continue;
+            }
+            let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            present.push(path.clone());
+            if self
+                .files
+                .get(&path)
+                .is_some_and(|cached| cached.modified == modified)
+            {
+                continue;
+            }
+            // Unreadable files, such as binaries, are left out like helpers
+            let parsed =
+                read_header(&path).map_or(Ok(None), |header| parse(&name, &path, dir, &header));
+            self.files.insert(path, CachedScript { modified, parsed });
+        }
+        self.files.retain(|path, _| present.contains(path));
+    }
+
+    /// Every script with a title, by title.
+    pub fn scripts(&self) -> Vec<&Script> {
+        let mut scripts: Vec<&Script> = self
+            .files
+            .values()
+            .filter_map(|cached| cached.parsed.as_ref().ok()?.as_ref())
+            .collect();
+        scripts.sort_by_key(|s| s.title.to_lowercase());
+        scripts
+    }
+
+    /// The script with file name `name`.
+    pub fn find(&self, name: &str) -> Option<&Script> {
+        self.scripts().into_iter().find(|s| s.name == name)
+    }
+
+    /// Scripts left out for a broken header, by path.
+    pub fn problems(&self) -> Vec<(&Path, &Diagnostic)> {
+        let mut problems: Vec<(&Path, &Diagnostic)> = self
+            .files
+            .iter()
+            .filter_map(|(path, cached)| Some((path.as_path(), cached.parsed.as_ref().err()?)))
+            .collect();
+        problems.sort_by_key(|(path, _)| *path);
+        problems
+    }
+}
+
+/// The lines at the top of a script up to its first line of code, which
+/// is all `parse` looks at.
+fn read_header(path: &Path) -> io::Result<String> {
+    let mut header = String::new();
+    for line in BufReader::new(std::fs::File::open(path)?).lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with("#!") && comment(trimmed).is_none() {
+            break;
+        }
+        header.push_str(&line);
+        header.push('\n');
+    }
+    Ok(header)
+}
+
+/// The text of a comment line in any of the languages scripts are written in.
+fn comment(line: &str) -> Option<&str> {
+    ["#", "//", "--"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Read the header comments at the top of a script. `None` when it has no
+/// title, so helper files can live next to scripts.
+fn parse(
+    name: &str,
+    path: &Path,
+    dir: &Path,
+    contents: &str,
+) -> Result<Option<Script>, Diagnostic> {
+    let mut script = Script {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        title: String::new(),
+        description: None,
+        icon: None,
+        arguments: Vec::new(),
+        mode: Mode::default(),
+        timeout: Duration::from_secs(DEFAULT_TIMEOUT),
+    };
+    let mut arguments = Vec::new();
+
+    for (number, line) in contents.lines().map(str::trim).enumerate() {
+        let error = |message: String| Diagnostic {
+            severity: Severity::Error,
+            line: number + 1,
+            column: 1,
+            message,
+        };
+        if line.is_empty() || line.starts_with("#!") {
+            continue;
+        }
+        let Some(comment) = comment(line) else {
+            // The header ends at the first line of code
+            break;
+        };
+        let comment = comment.trim();
+        let Some(field) = comment
+            .strip_prefix("@cheru.")
+            .or_else(|| comment.strip_prefix("@raycast."))
+        else {
+            continue;
+        };
+        let (key, value) = field.split_once(char::is_whitespace).unwrap_or((field, ""));
+        let value = value.trim();
+
+        match key {
+            "title" => script.title = value.to_string(),
+            "description" => script.description = Some(value.to_string()),
+            "icon" => script.icon = icon(value, dir),
+            "mode" => {
+                script.mode = match value {
+                    "silent" => Mode::Silent,
+                    "notification" | "compact" => Mode::Notification,
+                    "inline" | "fullOutput" => Mode::Inline,
+                    "clipboard" => Mode::Clipboard,
+                    other => {
+                        return Err(error(format!(
+                            "unknown mode `{}`, expected silent, notification, inline or clipboard",
+                            other
+                        )))
+                    }
+                }
+            }
+            "timeout" => match value.parse::<u64>() {
+                Ok(secs) if (1..=3600).contains(&secs) => {
+                    script.timeout = Duration::from_secs(secs)
+                }
+                _ => {
+                    return Err(error(format!(
+                        "`timeout` must be 1 to 3600 seconds, found `{}`",
+                        value
+                    )))
+                }
+            },
+            _ => {
+                let Some(number) = key
+                    .strip_prefix("argument")
+                    .and_then(|n| n.parse::<u32>().ok())
+                else {
+                    // schemaVersion, packageName and the like
+                    continue;
+                };
+                let argument = if value.starts_with('{') {
+                    serde_json::from_str(value)
+                        .map_err(|e| error(format!("invalid `{}`: {}", key, e)))?
+                } else {
+                    Argument {
+                        placeholder: value.to_string(),
+                        optional: false,
+                    }
+                };
+                arguments.push((number, argument));
+            }
+        }
+    }
+
+    if script.title.is_empty() {
+        return Ok(None);
+    }
+    arguments.sort_by_key(|(number, _)| *number);
+    script.arguments = arguments.into_iter().map(|(_, a)| a).collect();
+    Ok(Some(script))
+}
+
+/// Emoji are kept; image paths are made absolute. URLs aren't loaded.
+fn icon(value: &str, dir: &Path) -> Option<String> {
+    if value.is_empty() || value.contains("://") {
+        None
+    } else if value.contains('/') || value.contains('.') {
+        Some(dir.join(value).to_string_lossy().to_string())
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPLOY: &str = r#"#!/bin/bash
+# @cheru.title Deploy
+# @cheru.description Deploy a branch
+# @cheru.icon 🚀
+# @cheru.argument2 { "placeholder": "message", "optional": true }
+# @cheru.argument1 branch
+# @cheru.mode notification
+# @cheru.timeout 120
+
+git push staging "$1"
+# @cheru.title Not part of the header
+"#;
+
+    fn parse_str(contents: &str) -> Result<Option<Script>, Diagnostic> {
+        let dir = Path::new("/home/me/.config/cheru/scripts");
+        parse("deploy.sh", &dir.join("deploy.sh"), dir, contents)
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let script = parse_str(DEPLOY).unwrap().unwrap();
+        assert_eq!(script.title, "Deploy");
+        assert_eq!(script.description.as_deref(), Some("Deploy a branch"));
+        assert_eq!(script.icon.as_deref(), Some("🚀"));
+        assert_eq!(script.mode, Mode::Notification);
+        assert_eq!(script.timeout, Duration::from_secs(120));
+        let placeholders: Vec<&str> = script
+            .arguments
+            .iter()
+            .map(|a| a.placeholder.as_str())
+            .collect();
+        assert_eq!(placeholders, ["branch", "message"]);
+        assert!(script.arguments[1].optional);
+
+        let raycast = "#!/usr/bin/env python3\n\
+            # @raycast.schemaVersion 1\n\
+            # @raycast.title Toggle VPN\n\
+            # @raycast.mode fullOutput\n\
+            # @raycast.icon images/vpn.png\n";
+        let script = parse_str(raycast).unwrap().unwrap();
+        assert_eq!(script.mode, Mode::Inline);
+        assert_eq!(
+            script.icon.as_deref(),
+            Some("/home/me/.config/cheru/scripts/images/vpn.png")
+        );
+
+        assert_eq!(parse_str("#!/bin/sh\n# helper\necho hi\n"), Ok(None));
+        let error = parse_str("# @cheru.title X\n# @cheru.mode loud\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(parse_str("# @cheru.title X\n# @cheru.argument1 {oops\n").is_err());
+    }
+
+    #[test]
+    fn test_check_arguments() {
+        let script = parse_str(DEPLOY).unwrap().unwrap();
+        let args =
+            |values: &[&str]| -> Vec<String> { values.iter().map(|v| v.to_string()).collect() };
+        assert_eq!(
+            script.check_arguments(&[]),
+            Err("Deploy needs branch".to_string())
+        );
+        assert!(script.check_arguments(&args(&["main"])).is_ok());
+        assert!(script.check_arguments(&args(&["main", "hotfix"])).is_ok());
+        assert!(script.check_arguments(&args(&["a", "b", "c"])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_reads_executable_scripts() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("cheru-test-scripts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, contents: &str, mode: u32| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        write("b.sh", "# @cheru.title beta\n", 0o755);
+        write("a.sh", "# @cheru.title Alpha\n", 0o755);
+        write("notes.sh", "# @cheru.title Not executable\n", 0o644);
+        write("lib.sh", "helper() { :; }\n", 0o755);
+        write(
+            "broken.sh",
+            "# @cheru.title Broken\n# @cheru.timeout never\n",
+            0o755,
+        );
+
+        let mut cache = Cache::default();
+        cache.refresh_from(&dir);
+        let titles: Vec<&str> = cache.scripts().iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["Alpha", "beta"]);
+        let problems = cache.problems();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0, dir.join("broken.sh"));
+        assert_eq!(problems[0].1.line, 2);
+
+        // Only the header is read, and a changed file is read again
+        std::fs::write(dir.join("a.sh"), b"# @cheru.title Alpha 2\nexit 0\n\xff\n").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join("a.sh"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        std::fs::remove_file(dir.join("b.sh")).unwrap();
+        cache.refresh_from(&dir);
+        let titles: Vec<&str> = cache.scripts().iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["Alpha 2"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
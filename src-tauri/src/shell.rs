@@ -0,0 +1,297 @@
+//! `$` mode and script commands: programs run with their output captured
+//! line by line and a time limit.
+
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Lines reported per run; the rest are read and dropped.
+pub const MAX_LINES: usize = 1000;
+
+const POLL: Duration = Duration::from_millis(20);
+
+/// How long output may keep arriving once the command has exited, before
+/// what it left running in the background is no longer listened to.
+const DRAIN: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum ShellEvent {
+    Line {
+        stream: Stream,
+        line: String,
+    },
+    /// Always the last event of a run
+    Exit {
+        /// `None` when a signal ended it
+        code: Option<i32>,
+        timed_out: bool,
+        /// More than `MAX_LINES` lines were printed
+        truncated: bool,
+    },
+}
+
+/// The latest run's output, kept for Copy Output.
+#[derive(Debug, Default)]
+pub struct Capture {
+    pub run: u32,
+    pub command: String,
+    pub output: String,
+}
+
+/// `$SHELL`, or `/bin/sh` when it isn't set.
+pub fn user_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string())
+}
+
+/// Run `command` with `shell -c` in `dir`, as `spawn_command` does.
+pub fn spawn<F>(
+    shell: &str,
+    command: &str,
+    dir: &Path,
+    timeout: Duration,
+    on_event: F,
+) -> io::Result<()>
+where
+    F: Fn(ShellEvent) + Send + Sync + 'static,
+{
+    let mut cmd = Command::new(shell);
+    cmd.arg("-c").arg(command).current_dir(dir);
+    spawn_command(cmd, timeout, on_event)
+}
+
+/// Start `cmd` with its output captured. Returns once it has started;
+/// `on_event` then gets each output line and finally an `Exit`. After
+/// `timeout` the command and anything it started are killed. Background
+/// jobs it leaves behind when it exits keep running, unheard.
+pub fn spawn_command<F>(mut cmd: Command, timeout: Duration, on_event: F) -> io::Result<()>
+where
+    F: Fn(ShellEvent) + Send + Sync + 'static,
+{
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so a timeout can stop what it started
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd.spawn()?;
+
+    let on_event = Arc::new(on_event);
+    let count = Arc::new(AtomicUsize::new(0));
+    // Set once `Exit` is sent, so lines can't follow it
+    let closed = Arc::new(Mutex::new(false));
+    let readers = [
+        read_lines(
+            child.stdout.take(),
+            Stream::Stdout,
+            &on_event,
+            &count,
+            &closed,
+        ),
+        read_lines(
+            child.stderr.take(),
+            Stream::Stderr,
+            &on_event,
+            &count,
+            &closed,
+        ),
+    ];
+
+    thread::spawn(move || {
+        let deadline = Instant::now() + timeout;
+        let mut timed_out = false;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) if Instant::now() >= deadline => {
+                    timed_out = true;
+                    kill(&mut child);
+                    break child.wait().ok();
+                }
+                Ok(None) => thread::sleep(POLL),
+                Err(_) => break None,
+            }
+        };
+        // Background jobs may hold the pipes open long after the command
+        // exits; take what is already written and leave them be
+        let drained = Instant::now() + DRAIN;
+        while readers.iter().any(|r| !r.is_finished()) && Instant::now() < drained {
+            thread::sleep(POLL);
+        }
+        let mut closed = closed.lock().unwrap_or_else(|e| e.into_inner());
+        *closed = true;
+        on_event(ShellEvent::Exit {
+            code: status.and_then(|s| s.code()),
+            timed_out,
+            truncated: count.load(Ordering::Relaxed) > MAX_LINES,
+        });
+    });
+    Ok(())
+}
+
+fn read_lines<R, F>(
+    pipe: Option<R>,
+    stream: Stream,
+    on_event: &Arc<F>,
+    count: &Arc<AtomicUsize>,
+    closed: &Arc<Mutex<bool>>,
+) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: Fn(ShellEvent) + Send + Sync + 'static,
+{
+    let (on_event, count, closed) = (Arc::clone(on_event), Arc::clone(count), Arc::clone(closed));
+    thread::spawn(move || {
+        let Some(pipe) = pipe else {
+            return;
+        };
+        for line in BufReader::new(pipe).split(b'\n').map_while(Result::ok) {
+            let closed = closed.lock().unwrap_or_else(|e| e.into_inner());
+            if *closed {
+                break;
+            }
+            if count.fetch_add(1, Ordering::Relaxed) < MAX_LINES {
+                let line = String::from_utf8_lossy(&line);
+                on_event(ShellEvent::Line {
+                    stream,
+                    line: line.trim_end_matches('\r').to_string(),
+                });
+            }
+        }
+    })
+}
+
+/// Kill the command's whole process group.
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    // SAFETY: kill has no memory-safety preconditions; the group is the
+    // child's own, which hasn't been reaped yet
+    unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn run(command: &str, timeout: Duration) -> Vec<ShellEvent> {
+        let (tx, rx) = mpsc::channel();
+        spawn(
+            "/bin/sh",
+            command,
+            &std::env::temp_dir(),
+            timeout,
+            move |e| {
+                let _ = tx.send(e);
+            },
+        )
+        .unwrap();
+        let mut events = Vec::new();
+        while let Ok(event) = rx.recv_timeout(Duration::from_secs(10)) {
+            let done = matches!(event, ShellEvent::Exit { .. });
+            events.push(event);
+            if done {
+                break;
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_output_and_exit_code() {
+        let events = run("echo one; echo two >&2; exit 3", Duration::from_secs(10));
+        assert!(events.contains(&ShellEvent::Line {
+            stream: Stream::Stdout,
+            line: "one".to_string()
+        }));
+        assert!(events.contains(&ShellEvent::Line {
+            stream: Stream::Stderr,
+            line: "two".to_string()
+        }));
+        assert_eq!(
+            events.last(),
+            Some(&ShellEvent::Exit {
+                code: Some(3),
+                timed_out: false,
+                truncated: false
+            })
+        );
+
+        let events = run(
+            "i=0; while [ $i -lt 1005 ]; do echo $i; i=$((i+1)); done",
+            Duration::from_secs(10),
+        );
+        assert_eq!(events.len(), MAX_LINES + 1);
+        assert!(matches!(
+            events.last(),
+            Some(ShellEvent::Exit {
+                truncated: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_background_jobs_dont_hold_the_exit() {
+        let started = Instant::now();
+        let events = run("echo done; (sleep 1; echo late) &", Duration::from_secs(10));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(
+            events,
+            [
+                ShellEvent::Line {
+                    stream: Stream::Stdout,
+                    line: "done".to_string()
+                },
+                ShellEvent::Exit {
+                    code: Some(0),
+                    timed_out: false,
+                    truncated: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timeout_kills_the_group() {
+        let started = Instant::now();
+        let events = run(
+            "echo start; sleep 30 & sleep 30",
+            Duration::from_millis(300),
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events.last(),
+            Some(ShellEvent::Exit {
+                code: None,
+                timed_out: true,
+                ..
+            })
+        ));
+    }
+}
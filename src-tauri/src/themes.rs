@@ -0,0 +1,249 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+use crate::config;
+use crate::paths;
+
+/// Palettes defined in the frontend's themes.ts.
+pub const BUILTIN: &[&str] = &["gruvbox", "dark", "dracula", "one-dark"];
+
+/// Base for user themes that don't name one.
+const DEFAULT_BASE: &str = "dark";
+
+/// User themes may extend each other; give up on chains longer than this.
+const MAX_DEPTH: usize = 8;
+
+/// A fully resolved theme, ready for the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThemeConfig {
+    /// Built-in palette the colors are layered on
+    pub theme: String,
+    pub colors: HashMap<String, String>,
+    pub font_family: Option<String>,
+    /// Base font size in px
+    pub font_size: Option<u32>,
+    /// Window corner radius in px
+    pub radius: Option<u32>,
+    /// Used instead while the system prefers a light appearance
+    pub light: Option<Box<ThemeConfig>>,
+}
+
+/// One `themes/<name>.toml` file, before inheritance is applied.
+#[derive(Debug, Default)]
+struct ThemeFile {
+    extends: Option<String>,
+    colors: HashMap<String, String>,
+    font_family: Option<String>,
+    font_size: Option<u32>,
+    radius: Option<u32>,
+}
+
+pub fn themes_dir() -> PathBuf {
+    paths::config_dir().join("themes")
+}
+
+/// Built-in themes followed by user themes, by name.
+pub fn available() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN.iter().map(|s| s.to_string()).collect();
+    let mut user: Vec<String> = std::fs::read_dir(themes_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .filter(|name| !BUILTIN.contains(&name.as_str()))
+        .collect();
+    user.sort();
+    names.extend(user);
+    names
+}
+
+pub fn exists(name: &str) -> bool {
+    BUILTIN.contains(&name) || user_theme_path(name).is_some_and(|p| p.is_file())
+}
+
+fn user_theme_path(name: &str) -> Option<PathBuf> {
+    // Names come from config.toml; keep them inside the themes directory
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return None;
+    }
+    Some(themes_dir().join(format!("{}.toml", name)))
+}
+
+/// Resolve a theme by name, reading user themes from disk. Problems are
+/// returned alongside a best-effort result.
+pub fn resolve(name: &str) -> (ThemeConfig, Vec<String>) {
+    resolve_with(name, |n| {
+        user_theme_path(n).and_then(|p| std::fs::read_to_string(p).ok())
+    })
+}
+
+/// Resolve `name` by walking its `extends` chain down to a built-in palette.
+/// `read` returns the contents of a user theme file.
+fn resolve_with<F>(name: &str, read: F) -> (ThemeConfig, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut problems = Vec::new();
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut current = name.to_string();
+
+    let base = loop {
+        if BUILTIN.contains(&current.as_str()) {
+            break current;
+        }
+        if !seen.insert(current.clone()) {
+            problems.push(format!("theme `{}` extends itself", current));
+            break DEFAULT_BASE.to_string();
+        }
+        if chain.len() >= MAX_DEPTH {
+            problems.push(format!(
+                "theme `{}`: inheritance deeper than {}",
+                name, MAX_DEPTH
+            ));
+            break DEFAULT_BASE.to_string();
+        }
+        let Some(contents) = read(&current) else {
+            problems.push(format!("theme `{}` not found", current));
+            break DEFAULT_BASE.to_string();
+        };
+        let file = match parse_file(&contents) {
+            Ok((file, warnings)) => {
+                problems.extend(
+                    warnings
+                        .into_iter()
+                        .map(|w| format!("themes/{}.toml: {}", current, w)),
+                );
+                file
+            }
+            Err(e) => {
+                problems.push(format!("themes/{}.toml: {}", current, e));
+                ThemeFile::default()
+            }
+        };
+        let next = file
+            .extends
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BASE.to_string());
+        chain.push(file);
+        current = next;
+    };
+
+    // Apply from the base outwards so each theme overrides what it extends
+    let mut theme = ThemeConfig {
+        theme: base,
+        colors: HashMap::new(),
+        font_family: None,
+        font_size: None,
+        radius: None,
+        light: None,
+    };
+    for file in chain.into_iter().rev() {
+        theme.colors.extend(file.colors);
+        theme.font_family = file.font_family.or(theme.font_family);
+        theme.font_size = file.font_size.or(theme.font_size);
+        theme.radius = file.radius.or(theme.radius);
+    }
+    (theme, problems)
+}
+
+/// Parse a theme file, dropping invalid entries with a warning each.
+fn parse_file(contents: &str) -> Result<(ThemeFile, Vec<String>), String> {
+    let table: Table = contents
+        .parse()
+        .map_err(|e: toml::de::Error| e.message().trim().to_string())?;
+    let mut file = ThemeFile::default();
+    let mut warnings = Vec::new();
+
+    for (key, value) in &table {
+        match (key.as_str(), value) {
+            ("name", Value::String(_)) => {}
+            ("extends", Value::String(s)) => file.extends = Some(s.clone()),
+            ("font_family", Value::String(s)) if !s.contains([';', '{', '}']) => {
+                file.font_family = Some(s.clone())
+            }
+            ("font_size", Value::Integer(n)) if (8..=32).contains(n) => {
+                file.font_size = Some(*n as u32)
+            }
+            ("radius", Value::Integer(n)) if (0..=48).contains(n) => file.radius = Some(*n as u32),
+            ("colors", Value::Table(colors)) => {
+                for (name, color) in colors {
+                    match color.as_str() {
+                        Some(c) if config::is_valid_theme_color(name, c) => {
+                            file.colors.insert(name.clone(), c.to_string());
+                        }
+                        _ => warnings.push(format!("invalid color `{}`", name)),
+                    }
+                }
+            }
+            ("name" | "extends" | "font_family" | "font_size" | "radius" | "colors", _) => {
+                warnings.push(format!("invalid value for `{}`", key))
+            }
+            _ => warnings.push(format!("unknown key `{}`", key)),
+        }
+    }
+    Ok((file, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(files: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            files
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, c)| c.to_string())
+        }
+    }
+
+    #[test]
+    fn test_inherits_from_user_and_builtin_themes() {
+        let files = &[
+            ("nord", "extends = \"one-dark\"\nradius = 8\n[colors]\naccent = \"#88c0d0\"\ntext_primary = \"#eceff4\"\n"),
+            ("nord-big", "extends = \"nord\"\nfont_size = 16\n[colors]\naccent = \"#81a1c1\"\n"),
+        ];
+        let (theme, problems) = resolve_with("nord-big", lookup(files));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(theme.theme, "one-dark");
+        assert_eq!(theme.colors["accent"], "#81a1c1");
+        assert_eq!(theme.colors["text_primary"], "#eceff4");
+        assert_eq!(theme.radius, Some(8));
+        assert_eq!(theme.font_size, Some(16));
+    }
+
+    #[test]
+    fn test_invalid_entries_and_cycles() {
+        let files = &[
+            ("a", "extends = \"b\"\nfont_size = 200\n[colors]\naccent = \"nope\"\nborder = \"#333\"\n"),
+            ("b", "extends = \"a\"\n"),
+        ];
+        let (theme, problems) = resolve_with("a", lookup(files));
+        assert_eq!(theme.theme, DEFAULT_BASE);
+        assert_eq!(theme.font_size, None);
+        assert_eq!(theme.colors.get("border").map(String::as_str), Some("#333"));
+        assert!(!theme.colors.contains_key("accent"));
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+
+        let (theme, problems) = resolve_with("missing", lookup(files));
+        assert_eq!(theme.theme, DEFAULT_BASE);
+        assert_eq!(problems, vec!["theme `missing` not found".to_string()]);
+
+        // t0 extends t1, which extends t2, and so on without end
+        let endless = |name: &str| {
+            let n: usize = name.strip_prefix('t')?.parse().ok()?;
+            Some(format!("extends = \"t{}\"\n", n + 1))
+        };
+        let (theme, problems) = resolve_with("t0", endless);
+        assert_eq!(theme.theme, DEFAULT_BASE);
+        assert_eq!(
+            problems,
+            vec![format!("theme `t0`: inheritance deeper than {}", MAX_DEPTH)]
+        );
+    }
+}
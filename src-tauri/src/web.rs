@@ -0,0 +1,339 @@
+//! Quicklinks, web searches and `!bangs`: URL templates with a `{query}`
+//! placeholder.
+
+use crate::config::{Quicklink, WebConfig};
+
+/// Placeholder replaced by the search terms in a URL template.
+pub const PLACEHOLDER: &str = "{query}";
+
+/// Built-in engines for `[web] fallback`, as (keyword, name, URL template).
+pub const ENGINES: &[(&str, &str, &str)] = &[
+    (
+        "google",
+        "Google",
+        "https://www.google.com/search?q={query}",
+    ),
+    (
+        "duckduckgo",
+        "DuckDuckGo",
+        "https://duckduckgo.com/?q={query}",
+    ),
+    ("bing", "Bing", "https://www.bing.com/search?q={query}"),
+    (
+        "brave",
+        "Brave Search",
+        "https://search.brave.com/search?q={query}",
+    ),
+    (
+        "startpage",
+        "Startpage",
+        "https://www.startpage.com/do/search?q={query}",
+    ),
+    ("kagi", "Kagi", "https://kagi.com/search?q={query}"),
+];
+
+/// Bundled `!bangs`, as (trigger, site, URL template). `[bangs]` in the
+/// config adds to and overrides these.
+pub const BANGS: &[(&str, &str, &str)] = &[
+    ("a", "Amazon", "https://www.amazon.com/s?k={query}"),
+    (
+        "aw",
+        "ArchWiki",
+        "https://wiki.archlinux.org/index.php?search={query}",
+    ),
+    ("b", "Bing", "https://www.bing.com/search?q={query}"),
+    ("crates", "crates.io", "https://crates.io/search?q={query}"),
+    ("ddg", "DuckDuckGo", "https://duckduckgo.com/?q={query}"),
+    (
+        "docsrs",
+        "docs.rs",
+        "https://docs.rs/releases/search?query={query}",
+    ),
+    ("g", "Google", "https://www.google.com/search?q={query}"),
+    ("gh", "GitHub", "https://github.com/search?q={query}"),
+    (
+        "gi",
+        "Google Images",
+        "https://www.google.com/search?tbm=isch&q={query}",
+    ),
+    ("hn", "Hacker News", "https://hn.algolia.com/?q={query}"),
+    ("imdb", "IMDb", "https://www.imdb.com/find/?q={query}"),
+    (
+        "m",
+        "Google Maps",
+        "https://www.google.com/maps/search/{query}",
+    ),
+    (
+        "mdn",
+        "MDN",
+        "https://developer.mozilla.org/en-US/search?q={query}",
+    ),
+    ("npm", "npm", "https://www.npmjs.com/search?q={query}"),
+    (
+        "py",
+        "Python docs",
+        "https://docs.python.org/3/search.html?q={query}",
+    ),
+    ("r", "Reddit", "https://www.reddit.com/search/?q={query}"),
+    (
+        "rs",
+        "Rust std",
+        "https://doc.rust-lang.org/std/?search={query}",
+    ),
+    (
+        "so",
+        "Stack Overflow",
+        "https://stackoverflow.com/search?q={query}",
+    ),
+    (
+        "w",
+        "Wikipedia",
+        "https://en.wikipedia.org/wiki/Special:Search?search={query}",
+    ),
+    (
+        "wa",
+        "Wolfram Alpha",
+        "https://www.wolframalpha.com/input?i={query}",
+    ),
+    (
+        "wt",
+        "Wiktionary",
+        "https://en.wiktionary.org/wiki/Special:Search?search={query}",
+    ),
+    (
+        "yt",
+        "YouTube",
+        "https://www.youtube.com/results?search_query={query}",
+    ),
+];
+
+/// A query with a `!bang` in it, resolved.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BangSearch {
+    pub site: String,
+    /// The query without the bang; empty opens the site itself
+    pub terms: String,
+    pub url: String,
+}
+
+/// Resolve the first known `!bang` anywhere in `query`, looking in
+/// `overrides` before the bundled table. Unknown bangs stay in the terms.
+pub fn find_bang(query: &str, overrides: &[Quicklink]) -> Option<BangSearch> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let (i, site, template) = words.iter().enumerate().find_map(|(i, word)| {
+        let trigger = word.strip_prefix('!')?.to_lowercase();
+        let (site, template) = lookup_bang(&trigger, overrides)?;
+        Some((i, site, template))
+    })?;
+    let terms = words
+        .iter()
+        .enumerate()
+        .filter(|&(j, _)| j != i)
+        .map(|(_, word)| *word)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let url = if terms.is_empty() {
+        site_root(&template)
+    } else {
+        expand(&template, &terms)
+    };
+    Some(BangSearch { site, terms, url })
+}
+
+/// Site name and URL template for a bang trigger, without the `!`.
+pub fn lookup_bang(trigger: &str, overrides: &[Quicklink]) -> Option<(String, String)> {
+    if let Some(bang) = overrides
+        .iter()
+        .find(|b| b.keyword.eq_ignore_ascii_case(trigger))
+    {
+        return Some((bang.name.clone(), bang.url.clone()));
+    }
+    BANGS
+        .iter()
+        .find(|(t, _, _)| *t == trigger)
+        .map(|(_, site, url)| (site.to_string(), url.to_string()))
+}
+
+/// `https://host/` for a URL template, to open when a bang has no terms.
+fn site_root(template: &str) -> String {
+    let Some((scheme, rest)) = template.split_once("://") else {
+        return expand(template, "");
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    format!("{}://{}/", scheme, host)
+}
+
+/// The host a URL points at, to name sites that only have a URL.
+pub fn host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.strip_prefix("www.").unwrap_or(host))
+}
+
+/// Fill `template`'s placeholders with `terms`, percent-encoded.
+pub fn expand(template: &str, terms: &str) -> String {
+    template.replace(PLACEHOLDER, &encode(terms))
+}
+
+/// Whether a quicklink's URL takes search terms.
+pub fn takes_query(template: &str) -> bool {
+    template.contains(PLACEHOLDER)
+}
+
+/// Percent-encode everything but unreserved characters, so the terms stay
+/// one query value.
+fn encode(terms: &str) -> String {
+    let mut encoded = String::new();
+    for &byte in terms.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// The URL's scheme, lowercased; `None` when it has none.
+pub fn scheme(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Whether `url`'s scheme is one of `allowed`.
+pub fn is_allowed(url: &str, allowed: &[String]) -> bool {
+    scheme(url).is_some_and(|s| allowed.iter().any(|a| a.eq_ignore_ascii_case(&s)))
+}
+
+/// The quicklink whose keyword starts `query`, and the terms after it.
+pub fn keyword_search<'q>(
+    quicklinks: &'q [Quicklink],
+    query: &'q str,
+) -> Option<(&'q Quicklink, &'q str)> {
+    let (keyword, terms) = query.trim_start().split_once(char::is_whitespace)?;
+    let terms = terms.trim();
+    if terms.is_empty() {
+        return None;
+    }
+    quicklinks
+        .iter()
+        .find(|q| q.keyword.eq_ignore_ascii_case(keyword) && takes_query(&q.url))
+        .map(|q| (q, terms))
+}
+
+/// Name and URL template of the fallback engine: a quicklink with that
+/// keyword, else a built-in engine. Config validation makes one of them match.
+pub fn fallback(web: &WebConfig, quicklinks: &[Quicklink]) -> (String, String) {
+    if let Some(q) = quicklinks.iter().find(|q| q.keyword == web.fallback) {
+        return (q.name.clone(), q.url.clone());
+    }
+    let (_, name, url) = ENGINES
+        .iter()
+        .find(|(keyword, _, _)| *keyword == web.fallback)
+        .unwrap_or(&ENGINES[0]);
+    (name.to_string(), url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quicklink(keyword: &str, url: &str) -> Quicklink {
+        Quicklink {
+            name: keyword.to_uppercase(),
+            keyword: keyword.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expand_and_schemes() {
+        assert_eq!(
+            expand(
+                "https://docs.rs/releases/search?query={query}",
+                "tokio rt/ü&x"
+            ),
+            "https://docs.rs/releases/search?query=tokio%20rt%2F%C3%BC%26x"
+        );
+        assert_eq!(scheme("HTTPS://example.com").as_deref(), Some("https"));
+        assert_eq!(
+            scheme("obsidian://open?vault=x").as_deref(),
+            Some("obsidian")
+        );
+        assert_eq!(scheme("example.com/a:b"), None);
+        assert_eq!(scheme("/etc/passwd"), None);
+        let allowed = vec!["https".to_string()];
+        assert!(is_allowed("https://example.com", &allowed));
+        assert!(!is_allowed("http://example.com", &allowed));
+        assert!(!is_allowed("file:///etc/passwd", &allowed));
+    }
+
+    #[test]
+    fn test_keyword_search() {
+        let links = [
+            quicklink("gh", "https://github.com/search?q={query}"),
+            quicklink("mail", "https://mail.example.com"),
+        ];
+        let (link, terms) = keyword_search(&links, "GH  tauri  plugin ").unwrap();
+        assert_eq!(link.keyword, "gh");
+        assert_eq!(terms, "tauri  plugin");
+        assert!(keyword_search(&links, "gh ").is_none());
+        assert!(keyword_search(&links, "ghost town").is_none());
+        assert!(keyword_search(&links, "mail bob").is_none());
+
+        let mut web = WebConfig::default();
+        assert_eq!(fallback(&web, &links).0, "Google");
+        web.fallback = "gh".to_string();
+        assert_eq!(fallback(&web, &links).0, "GH");
+    }
+
+    #[test]
+    fn test_find_bang() {
+        let search = find_bang("how to  !W  borrow checker", &[]).unwrap();
+        assert_eq!(search.site, "Wikipedia");
+        assert_eq!(search.terms, "how to borrow checker");
+        assert_eq!(
+            search.url,
+            "https://en.wikipedia.org/wiki/Special:Search?search=how%20to%20borrow%20checker"
+        );
+
+        // Unknown bangs are kept as terms, and the first known one wins
+        let search = find_bang("!nope vec !rs !gh", &[]).unwrap();
+        assert_eq!(
+            (search.site.as_str(), search.terms.as_str()),
+            ("Rust std", "!nope vec !gh")
+        );
+
+        assert_eq!(
+            find_bang("!yt", &[]).unwrap().url,
+            "https://www.youtube.com/"
+        );
+        assert!(find_bang("hello world!", &[]).is_none());
+        assert!(find_bang("! g", &[]).is_none());
+
+        let overrides = [
+            quicklink("gh", "https://github.com/search?type=code&q={query}"),
+            quicklink("jira", "http://jira.internal/browse/{query}"),
+        ];
+        assert_eq!(
+            find_bang("!gh spawn", &overrides).unwrap().url,
+            "https://github.com/search?type=code&q=spawn"
+        );
+        assert_eq!(
+            find_bang("!jira CHERU-12", &overrides).unwrap().site,
+            "JIRA"
+        );
+        assert_eq!(find_bang("!g x", &overrides).unwrap().site, "Google");
+
+        assert_eq!(
+            host("https://www.example.com:8080/a?b"),
+            Some("example.com")
+        );
+        assert_eq!(host("http://user@jira.internal"), Some("jira.internal"));
+        assert_eq!(host("mailto:x"), None);
+    }
+}
@@ -1,11 +1,14 @@
-import { useRef, useCallback, useEffect } from "react";
+import { useRef, useCallback, useEffect, useState } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { applyTheme } from "./themes";
+import type { AppResult, ThemeConfig } from "./types/launcher";
 import { SearchBar } from "./components/SearchBar";
 import { ResultsList } from "./components/ResultsList";
 import { ActionBar } from "./components/ActionBar";
 import { PreviewPanel } from "./components/PreviewPanel";
+import { ActionMenu, ACTION_TYPES } from "./components/ActionMenu";
 import { useLauncher } from "./hooks/useLauncher";
 import "./App.css";
 
@@ -13,21 +16,40 @@ function App() {
   const inputRef = useRef<HTMLInputElement>(null);
   const isKeyboardNav = useRef(false);
   const lastMousePos = useRef({ x: 0, y: 0 });
+  // Query from a mode hotkey, applied once the window gains focus
+  const pendingQuery = useRef("");
+  // Result whose Cmd/Ctrl+K action menu is open
+  const [actionsFor, setActionsFor] = useState<AppResult | null>(null);
   const {
     query,
     results,
     selectedIndex,
     setSelectedIndex,
     browsePath,
+    hint,
     search,
     launch,
     moveSelection,
     hide,
   } = useLauncher();
 
+  const selectedResult = results[selectedIndex] ?? null;
+
+  const closeActions = useCallback(() => {
+    setActionsFor(null);
+    inputRef.current?.focus();
+  }, []);
+
   const handleKeyDown = useCallback(
     (e: React.KeyboardEvent) => {
       isKeyboardNav.current = true;
+      if ((e.metaKey || e.ctrlKey) && e.key.toLowerCase() === "k") {
+        e.preventDefault();
+        if (selectedResult && ACTION_TYPES.includes(selectedResult.result_type)) {
+          setActionsFor(selectedResult);
+        }
+        return;
+      }
       switch (e.key) {
         case "ArrowDown":
           e.preventDefault();
@@ -47,7 +69,7 @@ function App() {
           break;
       }
     },
-    [moveSelection, launch, hide]
+    [moveSelection, launch, hide, selectedResult]
   );
 
   // Refocus input and reload results when window becomes visible
@@ -60,12 +82,14 @@ function App() {
       .onFocusChanged(({ payload: focused }) => {
         if (cancelled) return;
         if (focused) {
+          const initial = pendingQuery.current;
+          pendingQuery.current = "";
           if (inputRef.current) {
             inputRef.current.focus();
-            inputRef.current.select();
+            if (!initial) inputRef.current.select();
           }
           // Repopulate results after hide() cleared them
-          search("");
+          search(initial);
         }
       })
       .then((fn) => {
@@ -82,14 +106,69 @@ function App() {
     };
   }, [search]);
 
-  // Load theme from config
+  // A mode hotkey (e.g. `files = "Alt+F"`) opens the launcher pre-filled
+  useEffect(() => {
+    let cancelled = false;
+    let unlistenFn: (() => void) | null = null;
+
+    listen<string>("launcher-query", ({ payload }) => {
+      if (document.hasFocus()) {
+        search(payload);
+        inputRef.current?.focus();
+      } else {
+        pendingQuery.current = payload;
+      }
+    }).then((fn) => {
+      if (cancelled) {
+        fn();
+      } else {
+        unlistenFn = fn;
+      }
+    });
+
+    return () => {
+      cancelled = true;
+      unlistenFn?.();
+    };
+  }, [search]);
+
+  // Load theme from config, and re-apply it whenever config.toml is saved
+  // or the system switches between light and dark
   useEffect(() => {
-    invoke<{ theme: string; colors: Record<string, string> }>("get_theme").then(
-      (cfg) => applyTheme(cfg.theme, cfg.colors)
-    );
+    let cancelled = false;
+    let unlistenFn: (() => void) | null = null;
+    let current: ThemeConfig | null = null;
+    const prefersLight = window.matchMedia("(prefers-color-scheme: light)");
+
+    const apply = () => {
+      if (!current) return;
+      applyTheme(prefersLight.matches && current.light ? current.light : current);
+    };
+
+    invoke<ThemeConfig>("get_theme").then((cfg) => {
+      current = cfg;
+      apply();
+    });
+    prefersLight.addEventListener("change", apply);
+
+    listen<ThemeConfig>("config-changed", ({ payload }) => {
+      current = payload;
+      apply();
+    }).then((fn) => {
+      if (cancelled) {
+        fn();
+      } else {
+        unlistenFn = fn;
+      }
+    });
+
+    return () => {
+      cancelled = true;
+      unlistenFn?.();
+      prefersLight.removeEventListener("change", apply);
+    };
   }, []);
 
-  const selectedResult = results[selectedIndex] ?? null;
   const showPreview = selectedResult?.result_type === "Image";
 
   return (
@@ -99,7 +178,7 @@ function App() {
           isKeyboardNav.current = false;
         }
       }}>
-      <SearchBar query={query} onQueryChange={search} inputRef={inputRef} />
+      <SearchBar query={query} hint={hint} onQueryChange={search} inputRef={inputRef} />
       {browsePath && (
         <div className="breadcrumb">
           {browsePath}
@@ -114,6 +193,16 @@ function App() {
           isKeyboardNav={isKeyboardNav}
         />
         {showPreview && <PreviewPanel result={selectedResult} />}
+        {actionsFor && (
+          <ActionMenu
+            result={actionsFor}
+            onClose={closeActions}
+            onDone={() => {
+              setActionsFor(null);
+              hide();
+            }}
+          />
+        )}
       </div>
       <ActionBar selectedResult={selectedResult} />
     </div>
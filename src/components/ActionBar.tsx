@@ -1,8 +1,11 @@
 import { useState, useEffect } from "react";
 import { getVersion } from "@tauri-apps/api/app";
 import type { AppResult } from "../types/launcher";
+import { ACTION_TYPES } from "./ActionMenu";
 import styles from "./ActionBar.module.css";
 
+const ACTIONS_KEY = navigator.platform.startsWith("Mac") ? "⌘K" : "Ctrl K";
+
 interface ActionBarProps {
   selectedResult: AppResult | null;
 }
@@ -19,7 +22,23 @@ export function ActionBar({ selectedResult }: ActionBarProps) {
       ? "Open Folder"
       : selectedResult.result_type === "Image"
         ? "Open Image"
-        : "Open Application"
+        : selectedResult.result_type === "Calculator" ||
+            selectedResult.result_type === "DateTime"
+          ? "Copy Result"
+          : selectedResult.result_type === "Config"
+            ? "Open Config"
+            : selectedResult.result_type === "Command" ||
+                selectedResult.result_type === "Shell"
+              ? "Run Command"
+              : selectedResult.result_type === "Output"
+                ? "Copy Line"
+                : selectedResult.result_type === "Script"
+                  ? "Run Script"
+                  : selectedResult.result_type === "WebSearch"
+                    ? "Open in Browser"
+                    : selectedResult.result_type === "Quicklink"
+                      ? "Search"
+                      : "Open Application"
     : "Open";
 
   return (
@@ -32,7 +51,11 @@ export function ActionBar({ selectedResult }: ActionBarProps) {
               ? "Application"
               : selectedResult.result_type === "Folder"
                 ? "Folder"
-                : "Image"}
+                : selectedResult.result_type === "Command"
+                  ? "Command"
+                  : selectedResult.result_type === "Script"
+                    ? "Script"
+                    : "Image"}
           </span>
         )}
       </div>
@@ -41,6 +64,12 @@ export function ActionBar({ selectedResult }: ActionBarProps) {
           <span className={styles.actionLabel}>{actionLabel}</span>
           <kbd className={styles.kbd}>↵</kbd>
         </div>
+        {selectedResult && ACTION_TYPES.includes(selectedResult.result_type) && (
+          <div className={styles.action}>
+            <span className={styles.actionLabel}>Actions</span>
+            <kbd className={styles.kbd}>{ACTIONS_KEY}</kbd>
+          </div>
+        )}
         <div className={styles.action}>
           <span className={styles.actionLabel}>Hide</span>
           <kbd className={styles.kbd}>esc</kbd>
@@ -0,0 +1,209 @@
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { ActionKind, AppResult, FileOpError, ResultAction, RunOutcome } from "../types/launcher";
+import styles from "./ActionMenu.module.css";
+
+// Result types get_actions has actions for
+export const ACTION_TYPES = ["App", "Folder", "Image", "File", "Command", "Shell"];
+
+// Actions that ask for an app, a name or a folder before running
+const CHOOSING: ActionKind[] = ["open_with", "rename", "move_to", "copy_to"];
+
+// Folder search starts at this many characters, as in the launcher
+const MIN_FOLDER_QUERY = 2;
+
+interface ActionMenuProps {
+  result: AppResult;
+  onClose: () => void;
+  /** An action ran; hide the launcher */
+  onDone: () => void;
+}
+
+export function ActionMenu({ result, onClose, onDone }: ActionMenuProps) {
+  const [actions, setActions] = useState<ResultAction[]>([]);
+  // The action waiting for a choice, and what it can choose from
+  const [pending, setPending] = useState<ActionKind | null>(null);
+  const [choices, setChoices] = useState<AppResult[]>([]);
+  const [filter, setFilter] = useState("");
+  const [selected, setSelected] = useState(0);
+  const [confirming, setConfirming] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  useEffect(() => {
+    invoke<ResultAction[]>("get_actions", { result }).then(setActions);
+    inputRef.current?.focus();
+  }, [result]);
+
+  useEffect(() => {
+    let cancelled = false;
+    const show = (found: AppResult[]) => {
+      if (!cancelled) setChoices(found);
+    };
+    if (pending === "open_with") {
+      invoke<AppResult[]>("get_open_with_apps", { path: result.exec, filter })
+        .then(show)
+        .catch((err) => setError(String(err)));
+    } else if (pending === "move_to" || pending === "copy_to") {
+      if (filter.length < MIN_FOLDER_QUERY) {
+        setChoices([]);
+      } else {
+        invoke<AppResult[]>("search_folders", { query: filter }).then(show);
+      }
+    }
+    return () => { cancelled = true; };
+  }, [pending, filter, result]);
+
+  const shown: { key: string; label: string; detail: string | null }[] =
+    pending === "rename"
+      ? filter && filter !== result.name
+        ? [{ key: filter, label: `Rename to “${filter}”`, detail: null }]
+        : []
+      : pending !== null
+        ? choices.map((c) => ({ key: c.exec, label: c.name, detail: c.description }))
+        : actions
+            .filter((a) => a.label.toLowerCase().includes(filter.toLowerCase()))
+            .map((a) => ({
+              key: a.kind,
+              label: confirming === a.kind ? `${a.label}? Press ↵ to confirm` : a.label,
+              detail: null,
+            }));
+
+  const execute = (kind: ActionKind, withApp: string | null) => {
+    invoke("execute_action", { kind, result, with: withApp })
+      .then(onDone)
+      .catch((err) => setError(String(err)));
+  };
+
+  // File operations have their own commands, with structured errors
+  const runFileOp = (kind: ActionKind, target: string | null) => {
+    const path = result.exec;
+    const call =
+      kind === "rename" ? invoke("rename_path", { path, name: target })
+      : kind === "move_to" ? invoke("move_path", { path, folder: target })
+      : kind === "copy_to" ? invoke("copy_path", { path, folder: target })
+      : kind === "duplicate" ? invoke("duplicate_path", { path })
+      : invoke("trash_path", { path });
+    call.then(onDone).catch((err: FileOpError) => setError(err.message));
+  };
+
+  // Commands outside the allowed folders run once confirmed
+  const runCommand = (kind: ActionKind) => {
+    invoke<RunOutcome>("run_command", {
+      command: result.exec,
+      terminal: kind === "run_in_terminal",
+      confirmed: confirming === kind,
+    })
+      .then((outcome) => {
+        if (outcome.status === "confirm") {
+          setConfirming(kind);
+          setError(`${outcome.path} is outside the allowed folders`);
+        } else {
+          onDone();
+        }
+      })
+      .catch((err) => setError(String(err)));
+  };
+
+  const backToActions = () => {
+    setPending(null);
+    setChoices([]);
+    setFilter("");
+    setSelected(0);
+  };
+
+  const choose = (index: number) => {
+    const item = shown[index];
+    if (!item) return;
+    if (pending === "open_with") {
+      execute("open_with", item.key);
+      return;
+    }
+    if (pending !== null) {
+      runFileOp(pending, item.key);
+      return;
+    }
+    const action = actions.find((a) => a.kind === item.key)!;
+    if (CHOOSING.includes(action.kind)) {
+      setPending(action.kind);
+      setChoices([]);
+      setFilter(action.kind === "rename" ? result.name : "");
+      setSelected(0);
+      setError(null);
+    } else if (action.destructive && confirming !== action.kind) {
+      setConfirming(action.kind);
+    } else if (action.kind === "run" || action.kind === "run_in_terminal") {
+      runCommand(action.kind);
+    } else if (action.kind === "duplicate" || action.kind === "trash") {
+      runFileOp(action.kind, null);
+    } else {
+      execute(action.kind, null);
+    }
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    // Keep the launcher's own navigation out of it
+    e.stopPropagation();
+    if (e.key === "ArrowDown" || e.key === "ArrowUp") {
+      e.preventDefault();
+      const step = e.key === "ArrowDown" ? 1 : -1;
+      setSelected((prev) => (prev + step + shown.length) % Math.max(shown.length, 1));
+      setConfirming(null);
+    } else if (e.key === "Enter") {
+      e.preventDefault();
+      choose(selected);
+    } else if (e.key === "Escape" || ((e.metaKey || e.ctrlKey) && e.key.toLowerCase() === "k")) {
+      e.preventDefault();
+      if (pending !== null && e.key === "Escape") {
+        backToActions();
+      } else {
+        onClose();
+      }
+    }
+  };
+
+  const placeholder =
+    pending === "open_with" ? `Open ${result.name} with…`
+    : pending === "rename" ? "New name"
+    : pending === "move_to" ? `Move ${result.name} to…`
+    : pending === "copy_to" ? `Copy ${result.name} to…`
+    : "Search actions...";
+
+  const empty =
+    pending === "rename" ? "Type a new name"
+    : (pending === "move_to" || pending === "copy_to") && filter.length < MIN_FOLDER_QUERY
+      ? "Type to search folders"
+      : "No matches";
+
+  return (
+    <div className={styles.menu} onKeyDown={handleKeyDown}>
+      <div className={styles.items}>
+        {shown.map((item, index) => (
+          <div
+            key={item.key}
+            className={`${styles.item} ${index === selected ? styles.selected : ""}`}
+            onMouseEnter={() => setSelected(index)}
+            onClick={() => choose(index)}
+          >
+            {item.label}
+            {item.detail && <span className={styles.detail}>{item.detail}</span>}
+          </div>
+        ))}
+        {shown.length === 0 && <div className={styles.empty}>{empty}</div>}
+      </div>
+      {error && <div className={styles.error}>{error}</div>}
+      <input
+        ref={inputRef}
+        className={styles.input}
+        value={filter}
+        onChange={(e) => {
+          setFilter(e.target.value);
+          setSelected(0);
+          setConfirming(null);
+        }}
+        placeholder={placeholder}
+        spellCheck={false}
+      />
+    </div>
+  );
+}
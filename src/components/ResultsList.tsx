@@ -17,15 +17,27 @@ interface Section {
 }
 
 const TYPE_LABELS: Record<string, string> = {
+  Config: "Config Problems",
   Calculator: "Calculator",
+  DateTime: "Date & Time",
   App: "Applications",
   System: "System",
   Folder: "Folders",
   Image: "Images",
   File: "Files",
   WebSearch: "Web Search",
+  Command: "Commands",
+  Shell: "Shell",
+  Output: "Output",
+  Script: "Scripts",
+  Quicklink: "Quicklinks",
 };
 
+// Script commands may use an emoji instead of an image
+function isEmojiIcon(result: AppResult): boolean {
+  return result.result_type === "Script" && !!result.icon && !result.icon.startsWith("/");
+}
+
 function groupByType(results: AppResult[]): Section[] {
   const sections: Section[] = [];
   let currentType: string | null = null;
@@ -61,7 +73,7 @@ export function ResultsList({
   const iconUrls = useMemo(() => {
     const urls = new Map<string, string>();
     for (const result of results) {
-      if (result.icon && !urls.has(result.icon)) {
+      if (result.icon && !isEmojiIcon(result) && !urls.has(result.icon)) {
         urls.set(result.icon, convertFileSrc(result.icon));
       }
     }
@@ -125,18 +137,40 @@ export function ResultsList({
                         ? "\u{1F5BC}"
                         : result.result_type === "Calculator"
                         ? "\u{1F5A9}"
+                        : result.result_type === "DateTime"
+                        ? "\u{1F552}"
                         : result.result_type === "System"
                         ? "\u{2699}"
                         : result.result_type === "WebSearch"
                         ? "\u{1F50D}"
+                        : result.result_type === "Quicklink"
+                        ? "\u{1F517}"
                         : result.result_type === "File"
                         ? "\u{1F4C4}"
+                        : result.result_type === "Config"
+                        ? "\u{26A0}"
+                        : result.result_type === "Command"
+                        ? "\u{276F}"
+                        : result.result_type === "Shell"
+                        ? "$"
+                        : result.result_type === "Output"
+                        ? "\u{2502}"
+                        : isEmojiIcon(result)
+                        ? result.icon
+                        : result.result_type === "Script"
+                        ? "\u{1F4DC}"
                         : result.name.charAt(0).toUpperCase()}
                     </span>
                   )}
                 </div>
                 <div className={styles.appInfo}>
-                  <span className={styles.appName}>{result.name}</span>
+                  <span
+                    className={`${styles.appName} ${
+                      result.result_type === "Output" ? styles.output : ""
+                    }`}
+                  >
+                    {result.name}
+                  </span>
                   {result.description && (
                     <span className={styles.appDescription}>
                       {result.description}
@@ -2,11 +2,12 @@ import styles from "./SearchBar.module.css";
 
 interface SearchBarProps {
   query: string;
+  hint: string | null;
   onQueryChange: (query: string) => void;
   inputRef: React.RefObject<HTMLInputElement | null>;
 }
 
-export function SearchBar({ query, onQueryChange, inputRef }: SearchBarProps) {
+export function SearchBar({ query, hint, onQueryChange, inputRef }: SearchBarProps) {
   return (
     <div className={styles.searchBar}>
       <svg
@@ -33,6 +34,7 @@ export function SearchBar({ query, onQueryChange, inputRef }: SearchBarProps) {
         spellCheck={false}
         autoFocus
       />
+      {hint && <span className={styles.hint}>{hint}</span>}
     </div>
   );
 }
@@ -1,6 +1,136 @@
 import { useState, useCallback, useRef, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
-import type { AppResult } from "../types/launcher";
+import type { Dispatch, SetStateAction } from "react";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import type {
+  AppResult,
+  CalcOutcome,
+  ConfigReport,
+  RunOutcome,
+  ScriptInfo,
+  ScriptMode,
+  ShellEvent,
+} from "../types/launcher";
+
+const CALC_HISTORY_PREFIX = "calc history";
+// "cheru:<key> <filter>" lists the choices for one setting
+const SETTINGS_PREFIX = "cheru:";
+// "!command args" runs a $PATH executable
+const COMMAND_PREFIX = "!";
+// "$ command" runs it with the user's shell and shows the output
+const SHELL_PREFIX = "$";
+// "script:<name> args" prompts for a script command's arguments
+const SCRIPT_PREFIX = "script:";
+
+type ShellExit = Extract<ShellEvent, { event: "exit" }>;
+
+interface ShellRun {
+  id: number;
+  command: string;
+  lines: { stream: "stdout" | "stderr"; line: string }[];
+  exit: ShellExit | null;
+}
+
+// Problems in config.toml and script headers, shown above the default results
+async function configIssues(): Promise<AppResult[]> {
+  const [config, scripts] = await Promise.all([
+    invoke<ConfigReport>("get_config_diagnostics"),
+    invoke<ConfigReport[]>("get_script_diagnostics"),
+  ]);
+  return [config, ...scripts].flatMap((report) =>
+    report.diagnostics.map((d) => ({
+      name: d.message,
+      exec: report.path,
+      icon: null,
+      description: `${report.path.split("/").pop()}:${d.line}:${d.column} (${d.severity.toLowerCase()})`,
+      result_type: "Config" as const,
+    })),
+  );
+}
+
+// Channel that feeds run `id`'s output into the state while it is the latest run
+function outputChannel(id: number, setRun: Dispatch<SetStateAction<ShellRun | null>>) {
+  const channel = new Channel<ShellEvent>();
+  channel.onmessage = (message) => {
+    setRun((prev) => {
+      if (!prev || prev.id !== id) return prev;
+      return message.event === "line"
+        ? { ...prev, lines: [...prev.lines, { stream: message.stream, line: message.line }] }
+        : { ...prev, exit: message };
+    });
+  };
+  return channel;
+}
+
+// How a run is going, or `idle` before it has started
+function runStatus(run: ShellRun | null, idle: string): string {
+  const exit = run?.exit ?? null;
+  let status = !run
+    ? idle
+    : !exit
+      ? "Running…"
+      : exit.timed_out
+        ? "Timed out"
+        : exit.code === null
+          ? "Killed"
+          : `Exited with ${exit.code}`;
+  if (run && exit?.truncated) {
+    status += ` · showing the first ${run.lines.length} lines`;
+  }
+  return status;
+}
+
+function outputRows(run: ShellRun | null): AppResult[] {
+  return (run?.lines ?? []).map((l, i) => ({
+    name: l.line,
+    exec: `output:${i}`,
+    icon: null,
+    description: l.stream === "stderr" ? "stderr" : null,
+    result_type: "Output",
+  }));
+}
+
+// The "$" command, followed by its output once it has run
+function shellResults(command: string, run: ShellRun | null): AppResult[] {
+  if (!command) return [];
+  const current = run?.command === command ? run : null;
+  return [
+    {
+      name: command,
+      exec: `shell:${command}`,
+      icon: null,
+      description: runStatus(current, "Enter runs it with $SHELL"),
+      result_type: "Shell",
+    },
+    ...outputRows(current),
+  ];
+}
+
+// "script:<name> <args>" split into the script's file name and its arguments
+function parseScriptQuery(q: string): { name: string; args: string } {
+  const rest = q.substring(SCRIPT_PREFIX.length);
+  const space = rest.indexOf(" ");
+  return space === -1
+    ? { name: rest, args: "" }
+    : { name: rest.substring(0, space), args: rest.substring(space + 1).trim() };
+}
+
+// A script's argument prompt, followed by its output for inline scripts
+function scriptResults(script: ScriptInfo, args: string, run: ShellRun | null): AppResult[] {
+  const current = run?.command === `${SCRIPT_PREFIX}${script.name} ${args}` ? run : null;
+  const usage = script.arguments
+    .map((a) => (a.optional ? `[${a.placeholder}]` : `<${a.placeholder}>`))
+    .join(" ");
+  return [
+    {
+      name: args ? `${script.title} ${args}` : script.title,
+      exec: `script-run:${script.name}`,
+      icon: script.icon,
+      description: runStatus(current, usage ? `${usage} · Enter runs it` : "Enter runs it"),
+      result_type: "Script",
+    },
+    ...outputRows(current),
+  ];
+}
 
 export function useLauncher() {
   const [query, setQuery] = useState("");
@@ -8,6 +138,13 @@ export function useLauncher() {
   const [selectedIndex, setSelectedIndex] = useState(0);
   const [isLoading, setIsLoading] = useState(false);
   const [browsePath, setBrowsePath] = useState<string | null>(null);
+  const [hint, setHint] = useState<string | null>(null);
+  // Command the user was asked to confirm; Enter on it again runs it
+  const [confirmCommand, setConfirmCommand] = useState<string | null>(null);
+  const [shellRun, setShellRun] = useState<ShellRun | null>(null);
+  // Script whose arguments are being typed
+  const [script, setScript] = useState<ScriptInfo | null>(null);
+  const shellRunId = useRef(0);
   const debounceRef = useRef<ReturnType<typeof setTimeout> | null>(null);
   const resolvedBasesRef = useRef<Map<string, string>>(new Map());
 
@@ -29,6 +166,7 @@ export function useLauncher() {
   const search = useCallback((q: string) => {
     setQuery(q);
     setSelectedIndex(0);
+    setConfirmCommand(null);
 
     if (debounceRef.current) {
       clearTimeout(debounceRef.current);
@@ -36,6 +174,7 @@ export function useLauncher() {
 
     debounceRef.current = setTimeout(async () => {
       setIsLoading(true);
+      setHint(null);
       try {
         if (q.startsWith(">")) {
           // Content search mode via ripgrep
@@ -48,7 +187,59 @@ export function useLauncher() {
           }
           const files = await invoke<AppResult[]>("search_file_contents", { query: contentQuery });
           setResults(files);
+        } else if (q.toLowerCase().startsWith(CALC_HISTORY_PREFIX)) {
+          // Calculator history mode
+          setBrowsePath(null);
+          const filter = q.substring(CALC_HISTORY_PREFIX.length).trim();
+          const history = await invoke<AppResult[]>("get_calc_history", { filter });
+          setResults(history);
+        } else if (q.startsWith(SETTINGS_PREFIX)) {
+          // Settings mode, entered from a "Cheru: ..." system entry
+          setBrowsePath(null);
+          const rest = q.substring(SETTINGS_PREFIX.length);
+          const space = rest.indexOf(" ");
+          const key = space === -1 ? rest : rest.substring(0, space);
+          const filter = space === -1 ? "" : rest.substring(space + 1).trim();
+          const choices = await invoke<AppResult[]>("get_setting_choices", { key, filter });
+          setResults(choices);
+        } else if (q.startsWith(SCRIPT_PREFIX)) {
+          // Script mode, entered from a script that takes arguments; the
+          // effect below shows the results
+          setBrowsePath(null);
+          try {
+            setScript(await invoke<ScriptInfo>("get_script", { name: parseScriptQuery(q).name }));
+          } catch (err) {
+            setScript(null);
+            setResults([]);
+            setHint(String(err));
+          }
+        } else if (q.startsWith(COMMAND_PREFIX)) {
+          // Command mode: the first word picks the executable, the rest are
+          // its arguments. A known "!bang" goes above the commands
+          setBrowsePath(null);
+          const bang = await invoke<AppResult | null>("resolve_bang", { query: q });
+          try {
+            const commands = await invoke<AppResult[]>("search_commands", {
+              query: q.substring(COMMAND_PREFIX.length),
+            });
+            setResults(bang ? [bang, ...commands] : commands);
+          } catch (err) {
+            setResults(bang ? [bang] : []);
+            if (!bang) setHint(String(err));
+          }
+        } else if (q.startsWith(SHELL_PREFIX)) {
+          // Shell mode: nothing runs before Enter; the effect below shows the results
+          setBrowsePath(null);
         } else if (q.includes("/")) {
+          // "tauri-apps/tauri !gh" is a search, not a path
+          const bang = await invoke<AppResult | null>("resolve_bang", { query: q });
+          if (bang) {
+            setResults([bang]);
+            setBrowsePath(null);
+            setIsLoading(false);
+            return;
+          }
+
           // Browse mode: parse path segments
           const slashIndex = q.indexOf("/");
           const firstSegment = q.substring(0, slashIndex);
@@ -64,7 +255,8 @@ export function useLauncher() {
           // Resolve the first segment to an actual path
           const basePath = await resolveFirstSegment(firstSegment);
           if (!basePath) {
-            setResults([]);
+            // Not a folder; "gh owner/repo" may still be a quicklink search
+            setResults(await invoke<AppResult[]>("search_quicklinks", { query: q }));
             setBrowsePath(null);
             setIsLoading(false);
             return;
@@ -100,43 +292,66 @@ export function useLauncher() {
         } else {
           // Normal search mode
           setBrowsePath(null);
-          const [apps, folders, images, calcResult] = await Promise.all([
+          const [bang, quicklinks, apps, scripts, folders, images, commands, calcResult, dateResult, issues] = await Promise.all([
+            invoke<AppResult | null>("resolve_bang", { query: q }),
+            invoke<AppResult[]>("search_quicklinks", { query: q }),
             invoke<AppResult[]>("search_apps", { query: q }),
+            invoke<AppResult[]>("search_scripts", { query: q }),
             invoke<AppResult[]>("search_folders", { query: q }),
             invoke<AppResult[]>("search_images", { query: q }),
-            invoke<string | null>("eval_expression", { expr: q }),
+            // Arguments need the "!" prefix; fails while commands are off
+            /\s/.test(q.trim())
+              ? Promise.resolve([])
+              : invoke<AppResult[]>("search_commands", { query: q }).catch(() => []),
+            invoke<CalcOutcome | null>("eval_expression", { expr: q }),
+            invoke<string | null>("eval_datetime", { query: q }),
+            q.trim() === "" ? configIssues() : Promise.resolve([]),
           ]);
           // Deduplicate by exec path
           const seen = new Set<string>();
-          const merged: AppResult[] = [];
+          const merged: AppResult[] = [...issues];
+
+          // A known "!bang" anywhere in the query goes first
+          if (bang) {
+            seen.add(bang.exec);
+            merged.push(bang);
+          }
 
-          // Prepend calculator result if available
-          if (calcResult) {
+          // Prepend calculator result if available; a failed expression only
+          // shows a hint so it doesn't displace real results
+          if (calcResult?.status === "error") {
+            setHint(calcResult.message);
+          } else if (calcResult) {
             merged.push({
-              name: `= ${calcResult}`,
-              exec: `calc:${calcResult}`,
+              name: `= ${calcResult.value}`,
+              exec: `calc:${calcResult.value}`,
               icon: null,
               description: q,
               result_type: "Calculator",
             });
           }
 
-          for (const r of [...apps, ...folders, ...images]) {
+          if (dateResult) {
+            merged.push({
+              name: dateResult,
+              exec: `datetime:${dateResult}`,
+              icon: null,
+              description: q,
+              result_type: "DateTime",
+            });
+          }
+
+          for (const r of [...quicklinks, ...apps, ...scripts, ...folders, ...images, ...commands]) {
             if (!seen.has(r.exec)) {
               seen.add(r.exec);
               merged.push(r);
             }
           }
 
-          // Web search fallback when few results
-          if (q.length >= 2 && merged.length < 3) {
-            merged.push({
-              name: `Search Google for "${q}"`,
-              exec: `https://www.google.com/search?q=${encodeURIComponent(q)}`,
-              icon: null,
-              description: "Open in browser",
-              result_type: "WebSearch",
-            });
+          // Web search fallback when few results, with the [web] fallback engine
+          if (!bang && q.length >= 2 && merged.length < 3) {
+            const fallback = await invoke<AppResult | null>("web_fallback", { query: q });
+            if (fallback && !seen.has(fallback.exec)) merged.push(fallback);
           }
 
           setResults(merged);
@@ -167,16 +382,99 @@ export function useLauncher() {
       return;
     }
 
+    // Settings entries open a list of choices instead of running anything
+    if (app.result_type === "System" && app.exec.startsWith("settings:")) {
+      search(`${SETTINGS_PREFIX}${app.exec.replace("settings:", "")} `);
+      return;
+    }
+
     try {
-      if (app.result_type === "Calculator") {
-        // Calculator results are display-only, do nothing on Enter
-        return;
+      if (app.result_type === "System" && app.exec.startsWith("config-set:")) {
+        const assignment = app.exec.replace("config-set:", "");
+        const eq = assignment.indexOf("=");
+        await invoke("set_config_value", {
+          key: assignment.substring(0, eq),
+          value: JSON.parse(assignment.substring(eq + 1)),
+        });
+      } else if (app.result_type === "Calculator") {
+        if (app.exec.startsWith("calc-history:")) {
+          await invoke("copy_to_clipboard", { text: app.exec.replace("calc-history:", "") });
+        } else {
+          // Bind ans/variables, record in history and copy the value
+          await invoke("commit_calculation", { expr: app.description });
+        }
+      } else if (app.result_type === "DateTime") {
+        await invoke("copy_to_clipboard", { text: app.name });
       } else if (app.result_type === "WebSearch") {
         await invoke("open_url", { url: app.exec });
+      } else if (app.result_type === "Quicklink") {
+        // Type the search terms after the keyword
+        search(`${app.exec.replace("quicklink:", "")} `);
+        return;
+      } else if (app.result_type === "Command") {
+        const outcome = await invoke<RunOutcome>("run_command", {
+          command: app.exec,
+          terminal: null,
+          confirmed: confirmCommand === app.exec,
+        });
+        if (outcome.status === "confirm") {
+          setConfirmCommand(app.exec);
+          setHint(`${outcome.path} is outside the allowed folders. Press ↵ again to run it`);
+          return;
+        }
+      } else if (app.result_type === "Shell") {
+        // Stay open to show the output as it arrives
+        const command = app.exec.replace("shell:", "");
+        const id = ++shellRunId.current;
+        const onOutput = outputChannel(id, setShellRun);
+        setShellRun({ id, command, lines: [], exit: null });
+        try {
+          await invoke("run_shell", { command, onOutput });
+        } catch (err) {
+          setShellRun(null);
+          throw err;
+        }
+        return;
+      } else if (app.result_type === "Script") {
+        const prompted = app.exec.startsWith("script-run:");
+        const { name, args } = prompted
+          ? parseScriptQuery(query)
+          : { name: app.exec.replace("script:", ""), args: "" };
+        if (!prompted) {
+          // Scripts that take arguments prompt for them first
+          const info = await invoke<ScriptInfo>("get_script", { name });
+          if (info.arguments.length > 0) {
+            search(`${SCRIPT_PREFIX}${name} `);
+            return;
+          }
+        }
+        const key = `${SCRIPT_PREFIX}${name} ${args}`;
+        const id = ++shellRunId.current;
+        const onOutput = outputChannel(id, setShellRun);
+        setShellRun({ id, command: key, lines: [], exit: null });
+        let mode: ScriptMode;
+        try {
+          mode = await invoke<ScriptMode>("run_script", { name, args, onOutput });
+        } catch (err) {
+          setShellRun(null);
+          throw err;
+        }
+        if (mode === "inline") {
+          // Stay open to show the output as it arrives
+          if (!query.startsWith(SCRIPT_PREFIX)) search(key);
+          return;
+        }
+      } else if (app.result_type === "Output") {
+        await invoke("copy_to_clipboard", { text: app.name });
       } else if (app.result_type === "System") {
         const id = app.exec.replace("system:", "");
         await invoke("run_system_command", { id });
-      } else if (app.result_type === "Folder" || app.result_type === "Image" || app.result_type === "File") {
+      } else if (
+        app.result_type === "Folder" ||
+        app.result_type === "Image" ||
+        app.result_type === "File" ||
+        app.result_type === "Config"
+      ) {
         await invoke("open_path", { path: app.exec });
       } else {
         await invoke("launch_app", { exec: app.exec });
@@ -186,10 +484,13 @@ export function useLauncher() {
       setResults([]);
       setSelectedIndex(0);
       setBrowsePath(null);
+      setHint(null);
+      setConfirmCommand(null);
     } catch (err) {
       console.error("Launch failed:", err);
+      setHint(String(err));
     }
-  }, [results, selectedIndex, browsePath, query, search]);
+  }, [results, selectedIndex, browsePath, query, search, confirmCommand]);
 
   const moveSelection = useCallback(
     (direction: "up" | "down") => {
@@ -211,6 +512,7 @@ export function useLauncher() {
       setResults([]);
       setSelectedIndex(0);
       setBrowsePath(null);
+      setHint(null);
     } catch (err) {
       console.error("Hide failed:", err);
     }
@@ -223,20 +525,34 @@ export function useLauncher() {
       invoke<AppResult[]>("search_apps", { query: "" }),
       invoke<AppResult[]>("search_folders", { query: "" }),
       invoke<AppResult[]>("search_images", { query: "" }),
-    ]).then(([apps, folders, images]) => {
+      configIssues(),
+    ]).then(([apps, folders, images, issues]) => {
       if (!cancelled) {
+        // Every issue points at config.toml, so only the rest is deduplicated
         const seen = new Set<string>();
-        const merged = [...apps, ...folders, ...images].filter((r) => {
+        const found = [...apps, ...folders, ...images].filter((r) => {
           if (seen.has(r.exec)) return false;
           seen.add(r.exec);
           return true;
         });
-        setResults(merged);
+        setResults([...issues, ...found]);
       }
     });
     return () => { cancelled = true; };
   }, []);
 
+  // Shell and script mode results follow the query and the latest run's output
+  useEffect(() => {
+    if (query.startsWith(SHELL_PREFIX)) {
+      setResults(shellResults(query.substring(SHELL_PREFIX.length).trim(), shellRun));
+    } else if (query.startsWith(SCRIPT_PREFIX)) {
+      const { name, args } = parseScriptQuery(query);
+      if (script?.name === name) {
+        setResults(scriptResults(script, args, shellRun));
+      }
+    }
+  }, [query, shellRun, script]);
+
   // Cleanup debounce timer on unmount
   useEffect(() => {
     return () => {
@@ -253,6 +569,7 @@ export function useLauncher() {
     setSelectedIndex,
     isLoading,
     browsePath,
+    hint,
     search,
     launch,
     moveSelection,
@@ -1,3 +1,5 @@
+import type { ThemeConfig } from "./types/launcher";
+
 export interface ThemeColors {
   bg_primary: string;
   bg_secondary: string;
@@ -80,12 +82,19 @@ export const themes: Record<string, ThemeColors> = {
   "one-dark": oneDark,
 };
 
-export function applyTheme(
-  themeName: string,
-  overrides: Record<string, string> = {}
-) {
-  const base = themes[themeName] ?? themes.dark;
-  const merged = { ...base, ...overrides };
+// Set a CSS variable, or fall back to the stylesheet default when unset
+function setOptional(name: string, value: string | null) {
+  const root = document.documentElement;
+  if (value === null) {
+    root.style.removeProperty(name);
+  } else {
+    root.style.setProperty(name, value);
+  }
+}
+
+export function applyTheme(cfg: ThemeConfig) {
+  const base = themes[cfg.theme] ?? themes.dark;
+  const merged = { ...base, ...cfg.colors };
   const root = document.documentElement;
 
   root.style.setProperty("--bg-primary", merged.bg_primary);
@@ -100,4 +109,8 @@ export function applyTheme(
   root.style.setProperty("--border", merged.border);
   root.style.setProperty("--border-light", merged.border_light);
   root.style.setProperty("--shadow", merged.shadow);
+
+  setOptional("--font-stack", cfg.font_family);
+  setOptional("--font-size", cfg.font_size === null ? null : `${cfg.font_size}px`);
+  setOptional("--radius", cfg.radius === null ? null : `${cfg.radius}px`);
 }
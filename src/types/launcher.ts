@@ -1,4 +1,4 @@
-export type ResultType = "App" | "Folder" | "Image" | "System" | "Calculator" | "WebSearch" | "File";
+export type ResultType = "App" | "Folder" | "Image" | "System" | "Calculator" | "DateTime" | "WebSearch" | "File" | "Config" | "Command" | "Shell" | "Output" | "Script" | "Quicklink";
 
 export interface AppResult {
   name: string;
@@ -7,3 +7,93 @@ export interface AppResult {
   description: string | null;
   result_type: ResultType;
 }
+
+export type CalcOutcome =
+  | { status: "ok"; value: string }
+  | { status: "error"; kind: string; position: number; message: string };
+
+/** From run_command: "confirm" when the program is outside the allowed folders */
+export type RunOutcome = { status: "started" } | { status: "confirm"; path: string };
+
+/** Messages from run_shell, in order; "exit" comes last */
+export type ShellEvent =
+  | { event: "line"; stream: "stdout" | "stderr"; line: string }
+  | { event: "exit"; code: number | null; timed_out: boolean; truncated: boolean };
+
+/** What happens with a script command's output */
+export type ScriptMode = "silent" | "notification" | "inline" | "clipboard";
+
+/** A script command, from get_script */
+export interface ScriptInfo {
+  /** File name in the scripts folder */
+  name: string;
+  title: string;
+  description: string | null;
+  icon: string | null;
+  arguments: { placeholder: string; optional: boolean }[];
+  mode: ScriptMode;
+}
+
+export interface ThemeConfig {
+  /** Built-in palette the colors are layered on */
+  theme: string;
+  colors: Record<string, string>;
+  font_family: string | null;
+  font_size: number | null;
+  radius: number | null;
+  /** Variant for when the system prefers a light appearance */
+  light: ThemeConfig | null;
+}
+
+export interface ConfigDiagnostic {
+  severity: "Error" | "Warning";
+  line: number;
+  column: number;
+  message: string;
+}
+
+export interface ConfigReport {
+  path: string;
+  diagnostics: ConfigDiagnostic[];
+}
+
+export type ActionKind =
+  | "open"
+  | "open_with"
+  | "reveal"
+  | "copy_path"
+  | "copy_name"
+  | "open_in_terminal"
+  | "rename"
+  | "duplicate"
+  | "move_to"
+  | "copy_to"
+  | "trash"
+  | "launch"
+  | "launch_new_instance"
+  | "show_desktop_file"
+  | "quit_instances"
+  | "run"
+  | "run_in_terminal"
+  | "copy_output";
+
+export interface ResultAction {
+  kind: ActionKind;
+  label: string;
+  /** Ask for confirmation before running */
+  destructive: boolean;
+}
+
+/** Error from the file operation commands */
+export interface FileOpError {
+  kind:
+    | "not_absolute"
+    | "not_found"
+    | "outside_home"
+    | "not_a_folder"
+    | "already_exists"
+    | "invalid_name"
+    | "into_itself"
+    | "io";
+  message: string;
+}